@@ -19,4 +19,151 @@ pub enum ErrorCode {
 
     #[msg("Option Not Expired")]
     OptionNotExpired,
+
+    #[msg("Switchboard Error")]
+    SwitchboardError,
+
+    #[msg("Oracle Price Stale")]
+    StaleOraclePrice,
+
+    #[msg("Oracle Confidence Too Wide")]
+    OracleConfidenceTooWide,
+
+    #[msg("Invalid Settlement Window")]
+    InvalidSettlementWindow,
+
+    #[msg("Settlement Window Not Open")]
+    SettlementWindowNotOpen,
+
+    #[msg("Price Sample Too Early")]
+    PriceSampleTooEarly,
+
+    #[msg("Insufficient Price Samples")]
+    InsufficientPriceSamples,
+
+    #[msg("Market Not Empty")]
+    MarketNotEmpty,
+
+    #[msg("Option Not American")]
+    NotAmerican,
+
+    #[msg("Invalid Exercise Mint")]
+    InvalidExerciseMint,
+
+    #[msg("Invalid Exercise Window")]
+    InvalidExerciseWindow,
+
+    #[msg("Wrong Settlement Style")]
+    WrongSettlementStyle,
+
+    #[msg("Exercise Window Closed")]
+    ExerciseWindowClosed,
+
+    #[msg("Exercise Window Open")]
+    ExerciseWindowOpen,
+
+    #[msg("Invalid Cap Strike")]
+    InvalidCapStrike,
+
+    #[msg("Invalid Binary Payout")]
+    InvalidBinaryPayout,
+
+    #[msg("Invalid Barrier")]
+    InvalidBarrier,
+
+    #[msg("Not Barrier")]
+    NotBarrier,
+
+    #[msg("Barrier Already Breached")]
+    BarrierAlreadyBreached,
+
+    #[msg("Barrier Not Breached")]
+    BarrierNotBreached,
+
+    #[msg("Option Knocked Out")]
+    OptionKnockedOut,
+
+    #[msg("Invalid Averaging")]
+    InvalidAveraging,
+
+    #[msg("Invalid Max Payout")]
+    InvalidMaxPayout,
+
+    #[msg("Invalid Series Params")]
+    InvalidSeriesParams,
+
+    #[msg("Series Full")]
+    SeriesFull,
+
+    #[msg("Invalid Schedule")]
+    InvalidSchedule,
+
+    #[msg("Schedule Not Due")]
+    ScheduleNotDue,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Collateral Mint Not Allowed")]
+    CollateralMintNotAllowed,
+
+    #[msg("Oracle Not Allowed")]
+    OracleNotAllowed,
+
+    #[msg("Invalid Expiry")]
+    InvalidExpiry,
+
+    #[msg("Allow List Full")]
+    AllowListFull,
+
+    #[msg("Invalid Fee")]
+    InvalidFee,
+
+    #[msg("Paused")]
+    Paused,
+
+    #[msg("Invalid Pause Flags")]
+    InvalidPauseFlags,
+
+    #[msg("Invalid Dispute Period")]
+    InvalidDisputePeriod,
+
+    #[msg("Dispute Window Open")]
+    DisputeWindowOpen,
+
+    #[msg("Dispute Window Closed")]
+    DisputeWindowClosed,
+
+    #[msg("Option Not Settled")]
+    OptionNotSettled,
+
+    #[msg("Invalid Price Sources")]
+    InvalidPriceSources,
+
+    #[msg("Insufficient Price Sources")]
+    InsufficientPriceSources,
+
+    #[msg("Math Overflow")]
+    MathOverflow,
+
+    #[msg("Divide By Zero")]
+    DivideByZero,
+
+    #[msg("Math Underflow")]
+    MathUnderflow,
+
+    #[msg("Options Exercised")]
+    OptionsExercised,
+
+    #[msg("Option Already Settled")]
+    OptionAlreadySettled,
+
+    #[msg("Price Samples Recorded")]
+    PriceSamplesRecorded,
+
+    #[msg("Grace Period Open")]
+    GracePeriodOpen,
+
+    #[msg("Invalid Oracle Guards")]
+    InvalidOracleGuards,
 }
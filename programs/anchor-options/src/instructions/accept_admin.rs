@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct AdminEvent {
+    config: Pubkey,
+    previous_admin: Pubkey,
+    admin: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    /// Protocol configuration
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The key the admin authority was handed over to
+    pub pending_admin: Signer<'info>,
+}
+
+/// Accept the admin authority handed over by the current admin
+pub fn handler(ctx: Context<AcceptAdmin>) -> ProgramResult {
+    let config = &mut ctx.accounts.config;
+
+    if config.pending_admin == Pubkey::default()
+        || config.pending_admin != ctx.accounts.pending_admin.key()
+    {
+        return Err(ErrorCode::Unauthorized.into());
+    }
+
+    let previous_admin = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    emit!(AdminEvent {
+        previous_admin,
+        config: config.key(),
+        admin: config.admin,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::convert::TryFrom;
+
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::instructions::init_option::*;
+use crate::state::*;
+
+#[event]
+pub struct AddStrikeEvent {
+    series: Pubkey,
+    market: Pubkey,
+    strike_price: u64,
+    num_strikes: u16,
+}
+
+#[derive(Accounts)]
+#[instruction(params: OptionParams)]
+pub struct AddStrike<'info> {
+    /// Series account the new market is added to
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = series.base_mint == option.base_mint.key(),
+        constraint = series.collateral_mint == option.collateral_mint.key(),
+        constraint = series.exercise_mint == option.exercise_mint.key(),
+        constraint = series.oracle_price == option.oracle_price.key(),
+    )]
+    pub series: Box<Account<'info, OptionSeries>>,
+
+    /// Signer, authority of the series
+    pub authority: Signer<'info>,
+
+    /// Accounts of the new market
+    pub option: InitializeOption<'info>,
+}
+
+/// Create the market of a series at a new strike,
+/// the parameters must be those of the series with the new strike price
+pub fn handler(ctx: Context<AddStrike>, params: OptionParams) -> ProgramResult {
+    let series = &mut ctx.accounts.series;
+
+    let expected = OptionParams {
+        strike_price: params.strike_price,
+        ..series.params.clone()
+    };
+    if params != expected {
+        return Err(ErrorCode::InvalidSeriesParams.into());
+    }
+
+    if series.strikes.len() >= series.max_strikes as usize {
+        return Err(ErrorCode::SeriesFull.into());
+    }
+
+    init_market(
+        &mut ctx.accounts.option,
+        &ctx.bumps,
+        &params,
+        ctx.remaining_accounts,
+    )?;
+
+    series.strikes.push(SeriesStrike {
+        strike_price: params.strike_price,
+        market: ctx.accounts.option.market.key(),
+    });
+
+    emit!(AddStrikeEvent {
+        strike_price: params.strike_price,
+        num_strikes: u16::try_from(series.strikes.len()).map_err(|_| ErrorCode::MathOverflow)?,
+        series: series.key(),
+        market: ctx.accounts.option.market.key(),
+    });
+
+    Ok(())
+}
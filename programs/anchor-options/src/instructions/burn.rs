@@ -37,7 +37,7 @@ pub struct BurnOptions<'info> {
     )]
     pub base_mint: Box<Account<'info, Mint>>,
 
-    /// Mint account for the collateral token (should be same as base_mint if the option is a call)
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
     #[account(
         constraint = market.collateral_mint == collateral_mint.key()
     )]
@@ -61,12 +61,6 @@ pub struct BurnOptions<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
-    #[account(
-        constraint = market.pyth_oracle_price == pyth_oracle_price.key()
-    )]
-    pub pyth_oracle_price: AccountInfo<'info>,
-
     /// The token account to burn the short option notes
     #[account(
         constraint = short_note_account.owner == depositor.key()
@@ -87,6 +81,13 @@ pub struct BurnOptions<'info> {
 
     /// Token program
     pub token_program: Program<'info, Token>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
 }
 
 impl<'info> BurnOptions<'info> {
@@ -124,33 +125,41 @@ impl<'info> BurnOptions<'info> {
     }
 }
 
-/// Burn long and short options to withdraw collateral before expiry
+/// Burn long and short options to withdraw collateral before expiry,
+/// less the share of the short notes in the payouts of options exercised early
 pub fn handler(ctx: Context<BurnOptions>, options: u64) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_BURN)?;
+
     if ctx.accounts.market.expiry_timestamp <= Clock::get()?.unix_timestamp {
         return Err(ErrorCode::OptionExpired.into());
     }
 
-    let oracle_data = ctx.accounts.pyth_oracle_price.try_borrow_data()?;
-
-    let oracle = match pyth_client::load_price(&oracle_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    let price = match oracle.get_current_price() {
-        None => return Err(ErrorCode::PriceError.into()),
-        Some(val) => val,
-    };
-
-    let collateral = calculate_collateral_amount(
-        options,
-        ctx.accounts.market.strike_price,
-        ctx.accounts.market.is_put,
-        ctx.accounts.collateral_mint.decimals,
-        ctx.accounts.base_mint.decimals,
-        price.expo,
-    );
+    // Physically exercised options took collateral out of the vault in exchange for proceeds only paid out
+    // at withdrawal, so writers can no longer exit early at the full collateral
+    if ctx.accounts.market.settlement_style == SettlementStyle::Physical
+        && ctx.accounts.market.exercised_options > 0
+    {
+        return Err(ErrorCode::OptionsExercised.into());
+    }
 
-    token::transfer(ctx.accounts.transfer_context(), collateral)?;
+    // Burned short notes bear their share of the payouts of options exercised early
+    let collateral = ctx
+        .accounts
+        .market
+        .collateral_amount(
+            options,
+            ctx.accounts.collateral_mint.decimals,
+            ctx.accounts.base_mint.decimals,
+            Rounding::Floor,
+        )?
+        .min(calculate_collateral(
+            options,
+            ctx.accounts.vault.amount,
+            ctx.accounts.short_note_mint.supply,
+            Rounding::Floor,
+        )?);
 
     let market = ctx.accounts.market.key();
     let seeds = &[
@@ -159,6 +168,11 @@ pub fn handler(ctx: Context<BurnOptions>, options: u64) -> ProgramResult {
         &[ctx.accounts.market.bumps.market_authority],
     ];
 
+    token::transfer(
+        ctx.accounts.transfer_context().with_signer(&[seeds]),
+        collateral,
+    )?;
+
     token::burn(
         ctx.accounts.short_note_burn_context().with_signer(&[seeds]),
         options,
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::math::*;
+use crate::state::*;
+
+#[event]
+pub struct SolvencyReport {
+    market: Pubkey,
+    vault_amount: u64,
+    required_collateral: u64,
+    exercise_vault_amount: u64,
+    required_proceeds: u64,
+    long_supply: u64,
+    short_supply: u64,
+    is_settled: bool,
+    is_solvent: bool,
+}
+
+#[derive(Accounts)]
+pub struct CheckInvariants<'info> {
+    /// Option account
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a long option
+    #[account(
+        constraint = market.long_note_mint == long_note_mint.key()
+    )]
+    pub long_note_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the token delivered by long holders on physical exercise
+    #[account(
+        constraint = market.exercise_mint == exercise_mint.key()
+    )]
+    pub exercise_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// Vault with custody over the tokens delivered on physical exercise
+    #[account(
+        constraint = market.exercise_vault == exercise_vault.key()
+    )]
+    pub exercise_vault: Box<Account<'info, TokenAccount>>,
+}
+
+/// Report whether the vaults of an option hold enough collateral and exercise proceeds for its outstanding notes
+pub fn handler(ctx: Context<CheckInvariants>) -> ProgramResult {
+    let market = &ctx.accounts.market;
+    let long_supply = ctx.accounts.long_note_mint.supply;
+    let short_supply = ctx.accounts.short_note_mint.supply;
+
+    let (required_collateral, required_proceeds, is_settled) = calculate_required_collateral(
+        market,
+        long_supply,
+        short_supply,
+        Clock::get()?.unix_timestamp,
+        ctx.accounts.collateral_mint.decimals,
+        ctx.accounts.exercise_mint.decimals,
+        ctx.accounts.base_mint.decimals,
+    )?;
+
+    emit!(SolvencyReport {
+        market: market.key(),
+        vault_amount: ctx.accounts.vault.amount,
+        required_collateral,
+        exercise_vault_amount: ctx.accounts.exercise_vault.amount,
+        required_proceeds,
+        long_supply,
+        short_supply,
+        is_settled,
+        is_solvent: ctx.accounts.vault.amount >= required_collateral
+            && ctx.accounts.exercise_vault.amount >= required_proceeds,
+    });
+
+    Ok(())
+}
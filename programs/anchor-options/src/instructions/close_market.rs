@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct CloseEvent {
+    market: Pubkey,
+    rent_payer: Pubkey,
+    dust: u64,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    /// Option account
+    #[account(
+        mut,
+        close = rent_payer,
+    )]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// PDA which has authority over all assets in the market
+    #[account(
+        constraint = market.market_authority == market_authority.key()
+    )]
+    pub market_authority: AccountInfo<'info>,
+
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a long option
+    #[account(
+        constraint = market.long_note_mint == long_note_mint.key()
+    )]
+    pub long_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        mut,
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// Vault with custody over the tokens delivered on physical exercise
+    #[account(
+        mut,
+        constraint = market.exercise_vault == exercise_vault.key()
+    )]
+    pub exercise_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        mut,
+        seeds = [b"fee_vault", market.collateral_mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        mut,
+        close = rent_payer,
+        constraint = market.settlement == settlement.key()
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account which paid the rent when the option was initialized
+    #[account(
+        mut,
+        constraint = market.rent_payer == rent_payer.key()
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CloseMarket<'info> {
+    fn dust_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn close_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault.to_account_info(),
+                destination: self.rent_payer.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn close_exercise_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.exercise_vault.to_account_info(),
+                destination: self.rent_payer.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Close an expired option once all notes are burned, returning the rent,
+/// the rounding dust left in the vault goes to the fee vault
+pub fn handler(ctx: Context<CloseMarket>) -> ProgramResult {
+    if ctx.accounts.market.expiry_timestamp > Clock::get()?.unix_timestamp {
+        return Err(ErrorCode::OptionNotExpired.into());
+    }
+
+    if ctx.accounts.short_note_mint.supply != 0
+        || ctx.accounts.long_note_mint.supply != 0
+        || ctx.accounts.exercise_vault.amount != 0
+    {
+        return Err(ErrorCode::MarketNotEmpty.into());
+    }
+
+    let market = ctx.accounts.market.key();
+    let seeds = &[
+        b"market_authority",
+        market.as_ref(),
+        &[ctx.accounts.market.bumps.market_authority],
+    ];
+
+    // Every note was paid out with rounding down, so whatever is left belongs to nobody
+    let dust = ctx.accounts.vault.amount;
+    if dust > 0 {
+        token::transfer(ctx.accounts.dust_context().with_signer(&[seeds]), dust)?;
+    }
+
+    token::close_account(ctx.accounts.close_vault_context().with_signer(&[seeds]))?;
+    token::close_account(
+        ctx.accounts
+            .close_exercise_vault_context()
+            .with_signer(&[seeds]),
+    )?;
+
+    emit!(CloseEvent {
+        dust,
+        market: ctx.accounts.market.key(),
+        rent_payer: ctx.accounts.rent_payer.key(),
+    });
+
+    Ok(())
+}
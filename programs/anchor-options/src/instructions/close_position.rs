@@ -0,0 +1,273 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::errors::ErrorCode;
+use crate::instructions::settle::settle_market;
+use crate::math::*;
+use crate::state::*;
+
+#[event]
+pub struct ClosePositionEvent {
+    market: Pubkey,
+    holder: Pubkey,
+    withdraw_account: Pubkey,
+    short_note_account: Pubkey,
+    long_note_account: Pubkey,
+    payout: u64,
+    collateral: u64,
+    fee: u64,
+    options: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(options: u64)]
+pub struct ClosePosition<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// PDA which has authority over all assets in the market
+    #[account(
+        constraint = market.market_authority == market_authority.key()
+    )]
+    pub market_authority: AccountInfo<'info>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a long option
+    #[account(
+        constraint = market.long_note_mint == long_note_mint.key()
+    )]
+    pub long_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        constraint = market.settlement == settlement.key()
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// The token account to burn the short option notes
+    #[account(
+        constraint = short_note_account.owner == holder.key()
+    )]
+    pub short_note_account: Box<Account<'info, TokenAccount>>,
+
+    /// The token account to burn the long option notes
+    #[account(
+        constraint = long_note_account.owner == holder.key()
+    )]
+    pub long_note_account: Box<Account<'info, TokenAccount>>,
+
+    /// The token account where to transfer withdrawn collateral to
+    pub withdraw_account: Box<Account<'info, TokenAccount>>,
+
+    /// Signer
+    pub holder: Signer<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ClosePosition<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.withdraw_account.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn fee_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn short_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.short_note_account.to_account_info(),
+                mint: self.short_note_mint.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn long_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.long_note_account.to_account_info(),
+                mint: self.long_note_mint.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Redeem long notes and withdraw the collateral of as many short notes after expiry in one transfer to the holder,
+/// the redeem fee is charged on the payout and the withdraw fee on the collateral
+pub fn handler(ctx: Context<ClosePosition>, options: u64) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_REDEEM)?;
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_WITHDRAW)?;
+
+    if ctx.accounts.market.settlement_style != SettlementStyle::Cash {
+        return Err(ErrorCode::WrongSettlementStyle.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if ctx.accounts.market.expiry_timestamp > now {
+        return Err(ErrorCode::OptionNotExpired.into());
+    }
+
+    let (payout, collateral) = if ctx.accounts.market.is_knocked_out() {
+        // Knocked out options are worthless, so the writer's share of the vault is all that is left
+        (
+            0,
+            calculate_collateral(
+                options,
+                ctx.accounts.vault.amount,
+                ctx.accounts.short_note_mint.supply,
+                Rounding::Floor,
+            )?,
+        )
+    } else {
+        settle_market(
+            &mut ctx.accounts.market,
+            &ctx.accounts.settlement,
+            &ctx.accounts.oracle_price,
+            ctx.remaining_accounts,
+            &ctx.accounts.config,
+            &ctx.accounts.vault,
+            &ctx.accounts.short_note_mint,
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.base_mint,
+        )?;
+
+        if ctx.accounts.market.dispute_end_timestamp > now {
+            return Err(ErrorCode::DisputeWindowOpen.into());
+        }
+
+        (
+            ctx.accounts.market.expired_value(
+                options,
+                ctx.accounts.market.expiry_price,
+                ctx.accounts.collateral_mint.decimals,
+                ctx.accounts.base_mint.decimals,
+                Rounding::Floor,
+            )?,
+            ctx.accounts.market.settled_collateral_amount(options)?,
+        )
+    };
+
+    let fee = calculate_fee(payout, ctx.accounts.config.redeem_fee_bps, Rounding::Ceil)?
+        .checked_add(calculate_fee(
+            collateral,
+            ctx.accounts.config.withdraw_fee_bps,
+            Rounding::Ceil,
+        )?)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let amount = payout
+        .checked_add(collateral)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let market = ctx.accounts.market.key();
+    let seeds = &[
+        b"market_authority",
+        market.as_ref(),
+        &[ctx.accounts.market.bumps.market_authority],
+    ];
+
+    if amount > fee {
+        token::transfer(
+            ctx.accounts.transfer_context().with_signer(&[seeds]),
+            amount - fee,
+        )?;
+    }
+
+    if fee > 0 {
+        token::transfer(ctx.accounts.fee_context().with_signer(&[seeds]), fee)?;
+    }
+
+    token::burn(
+        ctx.accounts.long_note_burn_context().with_signer(&[seeds]),
+        options,
+    )?;
+
+    token::burn(
+        ctx.accounts.short_note_burn_context().with_signer(&[seeds]),
+        options,
+    )?;
+
+    emit!(ClosePositionEvent {
+        payout,
+        collateral,
+        fee,
+        options,
+        market: ctx.accounts.market.key(),
+        holder: ctx.accounts.holder.key(),
+        withdraw_account: ctx.accounts.withdraw_account.key(),
+        short_note_account: ctx.accounts.short_note_account.key(),
+        long_note_account: ctx.accounts.long_note_account.key(),
+    });
+
+    Ok(())
+}
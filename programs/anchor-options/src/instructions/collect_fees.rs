@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Token, TokenAccount, Transfer},
+};
+
+use crate::state::*;
+
+#[event]
+pub struct CollectFeesEvent {
+    fee_vault: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct CollectFees<'info> {
+    /// Protocol configuration, the authority of every fee vault
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = admin,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The admin of the protocol
+    pub admin: Signer<'info>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", fee_vault.mint.as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The token account where to transfer the collected fees to
+    pub destination: Box<Account<'info, TokenAccount>>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CollectFees<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.fee_vault.to_account_info(),
+                to: self.destination.to_account_info(),
+                authority: self.config.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Transfer protocol fees out of a fee vault
+pub fn handler(ctx: Context<CollectFees>, amount: u64) -> ProgramResult {
+    let seeds = &[b"config".as_ref(), &[*ctx.bumps.get("config").unwrap()]];
+
+    token::transfer(
+        ctx.accounts.transfer_context().with_signer(&[seeds]),
+        amount,
+    )?;
+
+    emit!(CollectFeesEvent {
+        amount,
+        fee_vault: ctx.accounts.fee_vault.key(),
+        destination: ctx.accounts.destination.key(),
+    });
+
+    Ok(())
+}
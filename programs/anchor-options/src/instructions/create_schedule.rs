@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_spl::token::Mint;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct ScheduleEvent {
+    schedule: Pubkey,
+    authority: Pubkey,
+    base_mint: Pubkey,
+    collateral_mint: Pubkey,
+    exercise_mint: Pubkey,
+    oracle_price: Pubkey,
+    expiry_timestamp: i64,
+    cadence: i64,
+    strike_bps: Vec<u32>,
+    strike_increment: u64,
+    crank_reward: u64,
+    reward_deposit: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(params: OptionParams, schedule_params: ScheduleParams)]
+pub struct CreateSchedule<'info> {
+    /// Schedule account, derived from its authority, cadence and every parameter of its options but the strike and expiry
+    #[account(
+        init,
+        payer = authority,
+        space = Schedule::space(schedule_params.strike_bps.len()),
+        seeds = [
+            b"schedule",
+            authority.key().as_ref(),
+            base_mint.key().as_ref(),
+            collateral_mint.key().as_ref(),
+            exercise_mint.key().as_ref(),
+            oracle_price.key().as_ref(),
+            schedule_params.cadence.to_le_bytes().as_ref(),
+            &[
+                params.is_put as u8,
+                params.is_american as u8,
+                params.settlement_style as u8,
+                params.payout_kind as u8,
+                params.barrier_kind as u8,
+                params.is_asian as u8,
+            ],
+            params.cap_strike.to_le_bytes().as_ref(),
+            params.binary_payout.to_le_bytes().as_ref(),
+            params.barrier_price.to_le_bytes().as_ref(),
+            params.max_payout.to_le_bytes().as_ref(),
+            params.price_exponent.to_le_bytes().as_ref(),
+            params.guards_seed().as_ref(),
+        ],
+        bump,
+    )]
+    pub schedule: Box<Account<'info, Schedule>>,
+
+    /// Mint account for the base token
+    pub base_mint: Account<'info, Mint>,
+
+    /// Mint account for the collateral token
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// Mint account for the token delivered on physical exercise
+    pub exercise_mint: Account<'info, Mint>,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Signer, funds the schedule account and its crank rewards
+    pub authority: Signer<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a schedule listing options at a fixed cadence, starting with the expiry in the parameters
+pub fn handler(
+    ctx: Context<CreateSchedule>,
+    params: OptionParams,
+    schedule_params: ScheduleParams,
+) -> ProgramResult {
+    if params.expiry_timestamp <= Clock::get()?.unix_timestamp {
+        return Err(ErrorCode::OptionExpired.into());
+    }
+
+    if schedule_params.cadence <= 0
+        || schedule_params.strike_bps.is_empty()
+        || schedule_params.strike_bps.contains(&0)
+    {
+        return Err(ErrorCode::InvalidSchedule.into());
+    }
+
+    if schedule_params.reward_deposit > 0 {
+        invoke(
+            &system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &ctx.accounts.schedule.key(),
+                schedule_params.reward_deposit,
+            ),
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.schedule.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    let schedule = &mut ctx.accounts.schedule;
+    schedule.authority = ctx.accounts.authority.key();
+    schedule.base_mint = ctx.accounts.base_mint.key();
+    schedule.collateral_mint = ctx.accounts.collateral_mint.key();
+    schedule.exercise_mint = ctx.accounts.exercise_mint.key();
+    schedule.oracle_price = ctx.accounts.oracle_price.key();
+    schedule.params = OptionParams {
+        strike_price: 0,
+        ..params
+    };
+    schedule.cadence = schedule_params.cadence;
+    schedule.strike_bps = schedule_params.strike_bps.clone();
+    schedule.strike_increment = schedule_params.strike_increment;
+    schedule.crank_reward = schedule_params.crank_reward;
+    schedule.spot_price = 0;
+    schedule.num_listed = 0;
+
+    emit!(ScheduleEvent {
+        expiry_timestamp: params.expiry_timestamp,
+        cadence: schedule_params.cadence,
+        strike_bps: schedule_params.strike_bps,
+        strike_increment: schedule_params.strike_increment,
+        crank_reward: schedule_params.crank_reward,
+        reward_deposit: schedule_params.reward_deposit,
+        schedule: ctx.accounts.schedule.key(),
+        authority: ctx.accounts.authority.key(),
+        base_mint: ctx.accounts.base_mint.key(),
+        collateral_mint: ctx.accounts.collateral_mint.key(),
+        exercise_mint: ctx.accounts.exercise_mint.key(),
+        oracle_price: ctx.accounts.oracle_price.key(),
+    });
+
+    Ok(())
+}
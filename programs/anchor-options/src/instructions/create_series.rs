@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct SeriesEvent {
+    series: Pubkey,
+    authority: Pubkey,
+    base_mint: Pubkey,
+    collateral_mint: Pubkey,
+    exercise_mint: Pubkey,
+    oracle_price: Pubkey,
+    expiry_timestamp: i64,
+    is_put: bool,
+    max_strikes: u16,
+}
+
+#[derive(Accounts)]
+#[instruction(params: OptionParams, max_strikes: u16)]
+pub struct CreateSeries<'info> {
+    /// Series account, derived from its authority and every parameter of its options but the strike
+    #[account(
+        init,
+        payer = authority,
+        space = OptionSeries::space(max_strikes),
+        seeds = [
+            b"series",
+            authority.key().as_ref(),
+            base_mint.key().as_ref(),
+            collateral_mint.key().as_ref(),
+            exercise_mint.key().as_ref(),
+            oracle_price.key().as_ref(),
+            params.expiry_timestamp.to_le_bytes().as_ref(),
+            &[
+                params.is_put as u8,
+                params.is_american as u8,
+                params.settlement_style as u8,
+                params.payout_kind as u8,
+                params.barrier_kind as u8,
+                params.is_asian as u8,
+            ],
+            params.cap_strike.to_le_bytes().as_ref(),
+            params.binary_payout.to_le_bytes().as_ref(),
+            params.barrier_price.to_le_bytes().as_ref(),
+            params.max_payout.to_le_bytes().as_ref(),
+            params.price_exponent.to_le_bytes().as_ref(),
+            params.guards_seed().as_ref(),
+        ],
+        bump,
+    )]
+    pub series: Box<Account<'info, OptionSeries>>,
+
+    /// Mint account for the base token
+    pub base_mint: Account<'info, Mint>,
+
+    /// Mint account for the collateral token
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// Mint account for the token delivered on physical exercise
+    pub exercise_mint: Account<'info, Mint>,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Signer, allowed to add strikes to the series
+    pub authority: Signer<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a series of options sharing every parameter but the strike
+pub fn handler(
+    ctx: Context<CreateSeries>,
+    params: OptionParams,
+    max_strikes: u16,
+) -> ProgramResult {
+    if params.expiry_timestamp <= Clock::get()?.unix_timestamp {
+        return Err(ErrorCode::OptionExpired.into());
+    }
+
+    let series = &mut ctx.accounts.series;
+    series.authority = ctx.accounts.authority.key();
+    series.base_mint = ctx.accounts.base_mint.key();
+    series.collateral_mint = ctx.accounts.collateral_mint.key();
+    series.exercise_mint = ctx.accounts.exercise_mint.key();
+    series.oracle_price = ctx.accounts.oracle_price.key();
+    series.params = OptionParams {
+        strike_price: 0,
+        ..params
+    };
+    series.max_strikes = max_strikes;
+
+    emit!(SeriesEvent {
+        max_strikes,
+        expiry_timestamp: params.expiry_timestamp,
+        is_put: params.is_put,
+        series: ctx.accounts.series.key(),
+        authority: ctx.accounts.authority.key(),
+        base_mint: ctx.accounts.base_mint.key(),
+        collateral_mint: ctx.accounts.collateral_mint.key(),
+        exercise_mint: ctx.accounts.exercise_mint.key(),
+        oracle_price: ctx.accounts.oracle_price.key(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::errors::ErrorCode;
+use crate::math::*;
+use crate::state::*;
+
+#[event]
+pub struct ExerciseEvent {
+    market: Pubkey,
+    holder: Pubkey,
+    exercise_account: Pubkey,
+    long_note_account: Pubkey,
+    price: u64,
+    payout: u64,
+    fee: u64,
+    options: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(options: u64)]
+pub struct ExerciseOptions<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// PDA which has authority over all assets in the market
+    #[account(
+        constraint = market.market_authority == market_authority.key()
+    )]
+    pub market_authority: AccountInfo<'info>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a long option
+    #[account(
+        constraint = market.long_note_mint == long_note_mint.key()
+    )]
+    pub long_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// The token account to burn the long option notes
+    #[account(
+        constraint = long_note_account.owner == holder.key()
+    )]
+    pub long_note_account: Box<Account<'info, TokenAccount>>,
+
+    /// The token account where to transfer the payout to
+    pub exercise_account: Box<Account<'info, TokenAccount>>,
+
+    /// Signer
+    pub holder: Signer<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+}
+
+impl<'info> ExerciseOptions<'info> {
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.exercise_account.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn fee_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn long_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.long_note_account.to_account_info(),
+                mint: self.long_note_mint.to_account_info(),
+                authority: self.holder.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Exercise American options before expiry at the current oracle price, the redeem fee is taken out of the payout
+pub fn handler(ctx: Context<ExerciseOptions>, options: u64) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_REDEEM)?;
+
+    if ctx.accounts.market.settlement_style != SettlementStyle::Cash {
+        return Err(ErrorCode::WrongSettlementStyle.into());
+    }
+
+    if !ctx.accounts.market.is_american {
+        return Err(ErrorCode::NotAmerican.into());
+    }
+
+    let clock = Clock::get()?;
+    if ctx.accounts.market.expiry_timestamp <= clock.unix_timestamp {
+        return Err(ErrorCode::OptionExpired.into());
+    }
+
+    let price = ctx.accounts.market.oracle_kind.load_price(
+        &ctx.accounts.oracle_price,
+        ctx.accounts.market.price_exponent,
+    )?;
+
+    if price.price < 0 {
+        return Err(ErrorCode::PriceError.into());
+    }
+
+    price.check_guards(
+        clock.slot,
+        ctx.accounts.market.max_staleness_slots,
+        ctx.accounts.market.max_confidence_bps,
+    )?;
+
+    let payout = ctx.accounts.market.expired_value(
+        options,
+        price.price as u64,
+        ctx.accounts.collateral_mint.decimals,
+        ctx.accounts.base_mint.decimals,
+        Rounding::Floor,
+    )?;
+
+    // Exercising early is charged the same fee as redeeming at expiry
+    let fee = calculate_fee(payout, ctx.accounts.config.redeem_fee_bps, Rounding::Ceil)?;
+
+    let market = ctx.accounts.market.key();
+    let seeds = &[
+        b"market_authority",
+        market.as_ref(),
+        &[ctx.accounts.market.bumps.market_authority],
+    ];
+
+    if payout > fee {
+        token::transfer(
+            ctx.accounts.transfer_context().with_signer(&[seeds]),
+            payout - fee,
+        )?;
+    }
+
+    if fee > 0 {
+        token::transfer(ctx.accounts.fee_context().with_signer(&[seeds]), fee)?;
+    }
+
+    token::burn(ctx.accounts.long_note_burn_context(), options)?;
+
+    // The payout is charged to the short notes pro rata when they are burned or withdrawn
+    ctx.accounts.market.exercised_options = ctx
+        .accounts
+        .market
+        .exercised_options
+        .checked_add(options)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(ExerciseEvent {
+        payout,
+        fee,
+        options,
+        price: price.price as u64,
+        market: ctx.accounts.market.key(),
+        holder: ctx.accounts.holder.key(),
+        exercise_account: ctx.accounts.exercise_account.key(),
+        long_note_account: ctx.accounts.long_note_account.key(),
+    });
+
+    Ok(())
+}
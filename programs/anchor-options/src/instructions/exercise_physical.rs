@@ -0,0 +1,210 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token,
+    token::{Burn, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::errors::ErrorCode;
+use crate::math::*;
+use crate::state::*;
+
+#[event]
+pub struct ExercisePhysicalEvent {
+    market: Pubkey,
+    holder: Pubkey,
+    payment_account: Pubkey,
+    receive_account: Pubkey,
+    long_note_account: Pubkey,
+    delivered: u64,
+    received: u64,
+    options: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(options: u64)]
+pub struct ExercisePhysical<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// PDA which has authority over all assets in the market
+    #[account(
+        constraint = market.market_authority == market_authority.key()
+    )]
+    pub market_authority: AccountInfo<'info>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the token delivered on exercise (the quote token for a call, base_mint for a put)
+    #[account(
+        constraint = market.exercise_mint == exercise_mint.key()
+    )]
+    pub exercise_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a long option
+    #[account(
+        constraint = market.long_note_mint == long_note_mint.key()
+    )]
+    pub long_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// Vault with custody over the tokens delivered on exercise
+    #[account(
+        constraint = market.exercise_vault == exercise_vault.key()
+    )]
+    pub exercise_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The token account to burn the long option notes
+    #[account(
+        constraint = long_note_account.owner == holder.key()
+    )]
+    pub long_note_account: Box<Account<'info, TokenAccount>>,
+
+    /// The token account with the tokens delivered on exercise
+    pub payment_account: Box<Account<'info, TokenAccount>>,
+
+    /// The token account where to transfer the collateral received on exercise to
+    pub receive_account: Box<Account<'info, TokenAccount>>,
+
+    /// Signer
+    pub holder: Signer<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+}
+
+impl<'info> ExercisePhysical<'info> {
+    fn payment_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.payment_account.to_account_info(),
+                to: self.exercise_vault.to_account_info(),
+                authority: self.holder.to_account_info(),
+            },
+        )
+    }
+
+    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.receive_account.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn long_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                to: self.long_note_account.to_account_info(),
+                mint: self.long_note_mint.to_account_info(),
+                authority: self.holder.to_account_info(),
+            },
+        )
+    }
+}
+
+/// Exercise physically settled options by exchanging the strike for the base token
+pub fn handler(ctx: Context<ExercisePhysical>, options: u64) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_REDEEM)?;
+
+    if ctx.accounts.market.settlement_style != SettlementStyle::Physical {
+        return Err(ErrorCode::WrongSettlementStyle.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if !ctx.accounts.market.is_american && ctx.accounts.market.expiry_timestamp > now {
+        return Err(ErrorCode::OptionNotExpired.into());
+    }
+    if ctx.accounts.market.exercise_end_timestamp()? < now {
+        return Err(ErrorCode::ExerciseWindowClosed.into());
+    }
+
+    // A call holder pays the strike for the base token, a put holder delivers the base token for the strike,
+    // the strike is rounded in favour of the vault either way
+    let (delivered, received) = if ctx.accounts.market.is_put {
+        let strike = calculate_strike_amount(
+            options,
+            ctx.accounts.market.strike_price,
+            ctx.accounts.collateral_mint.decimals,
+            ctx.accounts.base_mint.decimals,
+            ctx.accounts.market.price_exponent,
+            Rounding::Floor,
+        )?;
+        (options, strike)
+    } else {
+        let strike = calculate_strike_amount(
+            options,
+            ctx.accounts.market.strike_price,
+            ctx.accounts.exercise_mint.decimals,
+            ctx.accounts.base_mint.decimals,
+            ctx.accounts.market.price_exponent,
+            Rounding::Ceil,
+        )?;
+        (strike, options)
+    };
+
+    token::transfer(ctx.accounts.payment_context(), delivered)?;
+
+    let market = ctx.accounts.market.key();
+    let seeds = &[
+        b"market_authority",
+        market.as_ref(),
+        &[ctx.accounts.market.bumps.market_authority],
+    ];
+
+    token::transfer(
+        ctx.accounts.transfer_context().with_signer(&[seeds]),
+        received,
+    )?;
+
+    token::burn(ctx.accounts.long_note_burn_context(), options)?;
+
+    ctx.accounts.market.exercised_options = ctx
+        .accounts
+        .market
+        .exercised_options
+        .checked_add(options)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(ExercisePhysicalEvent {
+        delivered,
+        received,
+        options,
+        market: ctx.accounts.market.key(),
+        holder: ctx.accounts.holder.key(),
+        payment_account: ctx.accounts.payment_account.key(),
+        receive_account: ctx.accounts.receive_account.key(),
+        long_note_account: ctx.accounts.long_note_account.key(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::program::AnchorOptions;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// Protocol configuration, a singleton PDA
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolConfig::space(),
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Signer, must be the upgrade authority of the program and becomes the admin of the protocol
+    pub admin: Signer<'info>,
+
+    /// The options program itself
+    #[account(
+        constraint = program.programdata_address() == Some(program_data.key())
+    )]
+    pub program: Program<'info, AnchorOptions>,
+
+    /// Program data account holding the upgrade authority of the program
+    pub program_data: Account<'info, ProgramData>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Initialize the protocol configuration, only the upgrade authority of the program can claim the admin role
+pub fn handler(
+    ctx: Context<InitializeConfig>,
+    min_expiry_distance: i64,
+    max_expiry_distance: i64,
+) -> ProgramResult {
+    if ctx.accounts.program_data.upgrade_authority_address != Some(ctx.accounts.admin.key()) {
+        return Err(ErrorCode::Unauthorized.into());
+    }
+
+    if min_expiry_distance < 0
+        || max_expiry_distance < 0
+        || (max_expiry_distance > 0 && max_expiry_distance < min_expiry_distance)
+    {
+        return Err(ErrorCode::InvalidExpiry.into());
+    }
+
+    ctx.accounts.config.admin = ctx.accounts.admin.key();
+    ctx.accounts.config.pending_admin = Pubkey::default();
+    ctx.accounts.config.collateral_mints = vec![];
+    ctx.accounts.config.oracles = vec![];
+    ctx.accounts.config.min_expiry_distance = min_expiry_distance;
+    ctx.accounts.config.max_expiry_distance = max_expiry_distance;
+    ctx.accounts.config.mint_fee_bps = 0;
+    ctx.accounts.config.redeem_fee_bps = 0;
+    ctx.accounts.config.withdraw_fee_bps = 0;
+    ctx.accounts.config.guardian = Pubkey::default();
+    ctx.accounts.config.paused = 0;
+    ctx.accounts.config.dispute_period = 0;
+    ctx.accounts.config.max_staleness_slots = 0;
+    ctx.accounts.config.max_confidence_bps = 0;
+    ctx.accounts.config.min_settlement_samples = 0;
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = config,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Protocol configuration, the authority of every fee vault
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Mint account for the collateral token
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Signer
+    pub payer: Signer<'info>,
+
+    /// Rent
+    pub rent: Sysvar<'info, Rent>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Initialize the vault collecting the protocol fees paid in a collateral token
+pub fn handler(_ctx: Context<InitializeFeeVault>) -> ProgramResult {
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::oracle::check_exponent;
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(expo: i32)]
+pub struct InitializeManualPrice<'info> {
+    /// Price account
+    #[account(
+        init,
+        payer = payer,
+    )]
+    pub manual_price: Box<Account<'info, ManualPrice>>,
+
+    /// The key allowed to post prices
+    pub authority: Signer<'info>,
+
+    /// Signer
+    pub payer: Signer<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Initialize an admin-posted price account
+pub fn handler(ctx: Context<InitializeManualPrice>, expo: i32) -> ProgramResult {
+    check_exponent(expo)?;
+
+    ctx.accounts.manual_price.authority = ctx.accounts.authority.key();
+    ctx.accounts.manual_price.price = 0;
+    ctx.accounts.manual_price.conf = 0;
+    ctx.accounts.manual_price.expo = expo;
+    ctx.accounts.manual_price.publish_slot = 0;
+
+    Ok(())
+}
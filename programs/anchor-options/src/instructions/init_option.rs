@@ -1,7 +1,10 @@
+use std::collections::BTreeMap;
+
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::errors::ErrorCode;
+use crate::oracle::check_exponent;
 use crate::state::*;
 
 #[event]
@@ -11,19 +14,51 @@ pub struct OptionEvent {
     collateral_mint: Pubkey,
     short_note_mint: Pubkey,
     long_note_mint: Pubkey,
-    pyth_oracle_price: Pubkey,
+    oracle_kind: OracleKind,
+    oracle_price: Pubkey,
+    fallback_sources: [PriceSource; MAX_FALLBACK_SOURCES],
+    settlement_quorum: u8,
+    price_exponent: i32,
     strike_price: u64,
+    cap_strike: u64,
     expiry_timestamp: i64,
     is_put: bool,
+    is_american: bool,
+    settlement_style: SettlementStyle,
+    payout_kind: PayoutKind,
+    binary_payout: u64,
+    max_payout: u64,
+    barrier_kind: BarrierKind,
+    barrier_price: u64,
+    exercise_mint: Pubkey,
+    exercise_window: i64,
+    max_staleness_slots: u64,
+    max_confidence_bps: u16,
+    settlement_window: i64,
+    min_settlement_samples: u32,
+    is_asian: bool,
+    min_sample_spacing: i64,
 }
 
 #[derive(Accounts)]
-#[instruction(strike_price: u64, expiry_timestamp: i64, is_put: bool)]
+#[instruction(params: OptionParams)]
 pub struct InitializeOption<'info> {
-    /// Option account
+    /// Option account, derived from the economic terms of the option so each option can only be listed once
     #[account(
         init,
         payer = payer,
+        seeds = [
+            b"market",
+            market_seed(
+                &base_mint.key(),
+                &collateral_mint.key(),
+                &exercise_mint.key(),
+                &oracle_price.key(),
+                &params,
+            )
+            .as_ref(),
+        ],
+        bump,
     )]
     pub market: Box<Account<'info, OptionMarket>>,
 
@@ -34,15 +69,38 @@ pub struct InitializeOption<'info> {
     )]
     pub market_authority: AccountInfo<'info>,
 
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token,
+    /// must be initialized before any market using the collateral token so fees can be charged
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
     /// Mint account for the base token
     pub base_mint: Account<'info, Mint>,
 
-    /// Mint account for the collateral token (should be same as base_mint if the option is a call)
+    /// Mint account for the collateral token
+    /// (should be same as base_mint if the option is a vanilla call without a max payout)
     #[account(
-        constraint = is_put || (base_mint.key() == collateral_mint.key())
+        constraint = params.is_put
+            || params.payout_kind == PayoutKind::Binary
+            || params.max_payout > 0
+            || (base_mint.key() == collateral_mint.key())
     )]
     pub collateral_mint: Account<'info, Mint>,
 
+    /// Mint account for the token delivered on physical exercise
+    /// (an allow-listed quote token for a call, base_mint for a put, collateral_mint if the option is cash settled)
+    pub exercise_mint: Box<Account<'info, Mint>>,
+
     /// Mint account for notes that represent a short option
     #[account(
         init,
@@ -76,11 +134,31 @@ pub struct InitializeOption<'info> {
     )]
     pub vault: Account<'info, TokenAccount>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
-    pub pyth_oracle_price: AccountInfo<'info>,
+    /// Vault with custody over the tokens delivered on physical exercise
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"exercise_vault", market.key().as_ref()],
+        bump,
+        token::mint = exercise_mint,
+        token::authority = market_authority
+    )]
+    pub exercise_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"settlement", market.key().as_ref()],
+        bump,
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
 
-    /// The account containing metadata about the Pyth oracle
-    pub pyth_oracle_product: AccountInfo<'info>,
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: AccountInfo<'info>,
+
+    /// The account containing metadata about the oracle (the Pyth product account, unused by other oracles)
+    pub oracle_product: AccountInfo<'info>,
 
     /// Signer
     pub payer: Signer<'info>,
@@ -95,88 +173,268 @@ pub struct InitializeOption<'info> {
     pub token_program: Program<'info, Token>,
 }
 
-/// Initialize a new option
-pub fn handler(
-    ctx: Context<InitializeOption>,
-    strike_price: u64,
-    expiry_timestamp: i64,
-    is_put: bool,
+/// Initialize a new option,
+/// the price and product accounts of its fallback price sources are passed as remaining accounts
+pub fn handler(ctx: Context<InitializeOption>, params: OptionParams) -> ProgramResult {
+    init_market(ctx.accounts, &ctx.bumps, &params, ctx.remaining_accounts)
+}
+
+/// Validates the parameters of a new option and initializes its market,
+/// shared by every instruction creating markets
+pub fn init_market(
+    accounts: &mut InitializeOption,
+    bumps: &BTreeMap<String, u8>,
+    params: &OptionParams,
+    remaining_accounts: &[AccountInfo],
 ) -> ProgramResult {
-    if expiry_timestamp <= Clock::get()?.unix_timestamp {
+    let now = Clock::get()?.unix_timestamp;
+    if params.expiry_timestamp <= now {
         return Err(ErrorCode::OptionExpired.into());
     }
 
-    // Validate pyth oracle
-    let product_data = ctx.accounts.pyth_oracle_product.try_borrow_data()?;
-    let product = match pyth_client::load_product(&product_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    if read_pyth_product_attribute(&product.attr, b"quote_currency").is_none() {
-        return Err(ErrorCode::InvalidProduct.into());
+    let config = &accounts.config;
+    let expiry_distance = params
+        .expiry_timestamp
+        .checked_sub(now)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if expiry_distance < config.min_expiry_distance
+        || (config.max_expiry_distance > 0 && expiry_distance > config.max_expiry_distance)
+    {
+        return Err(ErrorCode::InvalidExpiry.into());
     }
-    if product.px_acc.val[..] != ctx.accounts.pyth_oracle_price.key().to_bytes() {
-        return Err(ErrorCode::InvalidOracle.into());
+
+    if !config
+        .collateral_mints
+        .contains(&accounts.collateral_mint.key())
+    {
+        return Err(ErrorCode::CollateralMintNotAllowed.into());
     }
 
-    ctx.accounts.market.market_authority = ctx.accounts.market_authority.key();
-    ctx.accounts.market.base_mint = ctx.accounts.base_mint.key();
-    ctx.accounts.market.collateral_mint = ctx.accounts.collateral_mint.key();
-    ctx.accounts.market.short_note_mint = ctx.accounts.short_note_mint.key();
-    ctx.accounts.market.long_note_mint = ctx.accounts.long_note_mint.key();
-    ctx.accounts.market.vault = ctx.accounts.vault.key();
-    ctx.accounts.market.bumps = OptionBumps {
-        market_authority: *ctx.bumps.get("market_authority").unwrap(),
-        short_note_mint: *ctx.bumps.get("short_note_mint").unwrap(),
-        long_note_mint: *ctx.bumps.get("long_note_mint").unwrap(),
-        vault: *ctx.bumps.get("vault").unwrap(),
+    if !config.oracles.contains(&accounts.oracle_price.key()) {
+        return Err(ErrorCode::OracleNotAllowed.into());
+    }
+
+    if params.settlement_window < 0
+        || (params.settlement_window > 0 && params.min_settlement_samples == 0)
+    {
+        return Err(ErrorCode::InvalidSettlementWindow.into());
+    }
+
+    // Markets are keyed on their economic terms only, so the guards they settle with must meet those of the protocol
+    if (config.max_staleness_slots > 0
+        && (params.max_staleness_slots == 0
+            || params.max_staleness_slots > config.max_staleness_slots))
+        || (config.max_confidence_bps > 0
+            && (params.max_confidence_bps == 0
+                || params.max_confidence_bps > config.max_confidence_bps))
+        || (params.settlement_window > 0
+            && params.min_settlement_samples < config.min_settlement_samples)
+    {
+        return Err(ErrorCode::InvalidOracleGuards.into());
+    }
+
+    if params.min_sample_spacing < 0
+        || (params.settlement_window > 0 && params.min_sample_spacing >= params.settlement_window)
+        || (params.is_asian && (params.settlement_window == 0 || params.is_american))
+    {
+        return Err(ErrorCode::InvalidAveraging.into());
+    }
+
+    if params.cap_strike != 0
+        && (params.settlement_style != SettlementStyle::Cash
+            || (params.is_put && params.cap_strike >= params.strike_price)
+            || (!params.is_put && params.cap_strike <= params.strike_price))
+    {
+        return Err(ErrorCode::InvalidCapStrike.into());
+    }
+
+    let valid_payout = match params.payout_kind {
+        PayoutKind::Vanilla => params.binary_payout == 0,
+        PayoutKind::Binary => {
+            params.binary_payout > 0
+                && params.cap_strike == 0
+                && params.settlement_style == SettlementStyle::Cash
+        }
     };
+    if !valid_payout {
+        return Err(ErrorCode::InvalidBinaryPayout.into());
+    }
 
-    ctx.accounts.market.pyth_oracle_price = ctx.accounts.pyth_oracle_price.key();
-    ctx.accounts.market.strike_price = strike_price;
-    ctx.accounts.market.expiry_price = 0;
-    ctx.accounts.market.expiry_timestamp = expiry_timestamp;
-    ctx.accounts.market.is_put = is_put;
+    if params.max_payout > 0
+        && (params.is_put
+            || params.payout_kind != PayoutKind::Vanilla
+            || params.cap_strike != 0
+            || params.settlement_style != SettlementStyle::Cash
+            // The max payout is a quote amount, so the call must be collateralized in the quote token
+            || accounts.collateral_mint.key() == accounts.base_mint.key())
+    {
+        return Err(ErrorCode::InvalidMaxPayout.into());
+    }
 
-    emit!(OptionEvent {
-        strike_price,
-        expiry_timestamp,
-        is_put,
-        market: ctx.accounts.market.key(),
-        base_mint: ctx.accounts.base_mint.key(),
-        collateral_mint: ctx.accounts.collateral_mint.key(),
-        short_note_mint: ctx.accounts.short_note_mint.key(),
-        long_note_mint: ctx.accounts.long_note_mint.key(),
-        pyth_oracle_price: ctx.accounts.pyth_oracle_price.key(),
-    });
+    let valid_barrier = match params.barrier_kind {
+        BarrierKind::None => params.barrier_price == 0,
+        BarrierKind::KnockOut | BarrierKind::KnockIn => {
+            params.barrier_price > 0
+                && params.barrier_price != params.strike_price
+                && params.settlement_style == SettlementStyle::Cash
+        }
+    };
+    if !valid_barrier {
+        return Err(ErrorCode::InvalidBarrier.into());
+    }
 
-    Ok(())
-}
+    let exercise_mint = accounts.exercise_mint.key();
+    let valid_exercise = match params.settlement_style {
+        SettlementStyle::Cash => exercise_mint == accounts.collateral_mint.key(),
+        SettlementStyle::Physical if params.is_put => exercise_mint == accounts.base_mint.key(),
+        // Call holders pay the strike in a quote token the protocol allows, not one printed by the market creator
+        SettlementStyle::Physical => {
+            exercise_mint != accounts.base_mint.key()
+                && accounts.config.collateral_mints.contains(&exercise_mint)
+        }
+    };
+    if !valid_exercise {
+        return Err(ErrorCode::InvalidExerciseMint.into());
+    }
 
-fn read_pyth_product_attribute<'d>(data: &'d [u8], attribute: &[u8]) -> Option<&'d [u8]> {
-    let mut idx = 0;
+    if params.exercise_window < 0
+        || (params.settlement_style == SettlementStyle::Physical && params.exercise_window == 0)
+    {
+        return Err(ErrorCode::InvalidExerciseWindow.into());
+    }
+
+    params
+        .oracle_kind
+        .validate(&accounts.oracle_price, &accounts.oracle_product)?;
 
-    while idx < data.len() {
-        let key_len = data[idx] as usize;
-        idx += 1;
+    check_exponent(params.price_exponent)?;
 
-        if key_len == 0 {
+    // The price and product accounts of each fallback source are passed as pairs of remaining accounts
+    let mut fallback_accounts = remaining_accounts.chunks(2);
+    let mut num_sources = 1;
+    for (index, source) in params.fallback_sources.iter().enumerate() {
+        if source.oracle_price == Pubkey::default() {
             continue;
         }
 
-        let key = &data[idx..idx + key_len];
-        idx += key_len;
-
-        let val_len = data[idx] as usize;
-        idx += 1;
+        if source.oracle_price == accounts.oracle_price.key()
+            || params.fallback_sources[..index]
+                .iter()
+                .any(|other| other.oracle_price == source.oracle_price)
+        {
+            return Err(ErrorCode::InvalidPriceSources.into());
+        }
 
-        let value = &data[idx..idx + val_len];
-        idx += val_len;
+        if !accounts.config.oracles.contains(&source.oracle_price) {
+            return Err(ErrorCode::OracleNotAllowed.into());
+        }
 
-        if key == attribute {
-            return Some(value);
+        match fallback_accounts.next() {
+            Some([price_account, product_account])
+                if price_account.key() == source.oracle_price =>
+            {
+                source
+                    .oracle_kind
+                    .validate(price_account, product_account)?;
+            }
+            _ => return Err(ErrorCode::InvalidPriceSources.into()),
         }
+
+        num_sources += 1;
+    }
+
+    if params.settlement_quorum > num_sources {
+        return Err(ErrorCode::InvalidPriceSources.into());
     }
 
-    None
+    accounts.market.market_authority = accounts.market_authority.key();
+    accounts.market.base_mint = accounts.base_mint.key();
+    accounts.market.collateral_mint = accounts.collateral_mint.key();
+    accounts.market.short_note_mint = accounts.short_note_mint.key();
+    accounts.market.long_note_mint = accounts.long_note_mint.key();
+    accounts.market.vault = accounts.vault.key();
+    accounts.market.exercise_mint = exercise_mint;
+    accounts.market.exercise_vault = accounts.exercise_vault.key();
+    accounts.market.settlement = accounts.settlement.key();
+    accounts.market.bumps = OptionBumps {
+        market: *bumps.get("market").unwrap(),
+        market_authority: *bumps.get("market_authority").unwrap(),
+        short_note_mint: *bumps.get("short_note_mint").unwrap(),
+        long_note_mint: *bumps.get("long_note_mint").unwrap(),
+        vault: *bumps.get("vault").unwrap(),
+        exercise_vault: *bumps.get("exercise_vault").unwrap(),
+        settlement: *bumps.get("settlement").unwrap(),
+    };
+
+    accounts.market.rent_payer = accounts.payer.key();
+
+    accounts.market.oracle_kind = params.oracle_kind;
+    accounts.market.fallback_sources = params.fallback_sources;
+    accounts.market.settlement_quorum = params.settlement_quorum;
+    accounts.market.settlement_sources = 0;
+    accounts.market.oracle_price = accounts.oracle_price.key();
+    accounts.market.price_exponent = params.price_exponent;
+    accounts.market.strike_price = params.strike_price;
+    accounts.market.cap_strike = params.cap_strike;
+    accounts.market.expiry_price = 0;
+    accounts.market.original_expiry_price = 0;
+    accounts.market.dispute_end_timestamp = 0;
+    accounts.market.settled_collateral = 0;
+    accounts.market.settled_options = 0;
+    accounts.market.settled_payout = 0;
+    accounts.market.expiry_timestamp = params.expiry_timestamp;
+    accounts.market.is_put = params.is_put;
+    accounts.market.is_american = params.is_american;
+    accounts.market.settlement_style = params.settlement_style;
+    accounts.market.payout_kind = params.payout_kind;
+    accounts.market.binary_payout = params.binary_payout;
+    accounts.market.max_payout = params.max_payout;
+    accounts.market.barrier_kind = params.barrier_kind;
+    accounts.market.barrier_price = params.barrier_price;
+    accounts.market.knocked = false;
+    accounts.market.exercise_window = params.exercise_window;
+    accounts.market.exercised_options = 0;
+    accounts.market.max_staleness_slots = params.max_staleness_slots;
+    accounts.market.max_confidence_bps = params.max_confidence_bps;
+    accounts.market.settlement_window = params.settlement_window;
+    accounts.market.min_settlement_samples = params.min_settlement_samples;
+    accounts.market.paused = 0;
+    accounts.market.is_asian = params.is_asian;
+    accounts.market.min_sample_spacing = params.min_sample_spacing;
+
+    accounts.settlement.market = accounts.market.key();
+
+    emit!(OptionEvent {
+        price_exponent: params.price_exponent,
+        strike_price: params.strike_price,
+        cap_strike: params.cap_strike,
+        expiry_timestamp: params.expiry_timestamp,
+        is_put: params.is_put,
+        is_american: params.is_american,
+        settlement_style: params.settlement_style,
+        payout_kind: params.payout_kind,
+        binary_payout: params.binary_payout,
+        max_payout: params.max_payout,
+        barrier_kind: params.barrier_kind,
+        barrier_price: params.barrier_price,
+        exercise_mint,
+        exercise_window: params.exercise_window,
+        oracle_kind: params.oracle_kind,
+        fallback_sources: params.fallback_sources,
+        settlement_quorum: params.settlement_quorum,
+        max_staleness_slots: params.max_staleness_slots,
+        max_confidence_bps: params.max_confidence_bps,
+        settlement_window: params.settlement_window,
+        min_settlement_samples: params.min_settlement_samples,
+        is_asian: params.is_asian,
+        min_sample_spacing: params.min_sample_spacing,
+        market: accounts.market.key(),
+        base_mint: accounts.base_mint.key(),
+        collateral_mint: accounts.collateral_mint.key(),
+        short_note_mint: accounts.short_note_mint.key(),
+        long_note_mint: accounts.long_note_mint.key(),
+        oracle_price: accounts.oracle_price.key(),
+    });
+
+    Ok(())
 }
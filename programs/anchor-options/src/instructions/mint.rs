@@ -16,6 +16,7 @@ pub struct MintEvent {
     short_note_account: Pubkey,
     long_note_account: Pubkey,
     collateral: u64,
+    fee: u64,
     options: u64,
 }
 
@@ -37,7 +38,7 @@ pub struct MintOptions<'info> {
     )]
     pub base_mint: Box<Account<'info, Mint>>,
 
-    /// Mint account for the collateral token (should be same as base_mint if the option is a call)
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
     #[account(
         constraint = market.collateral_mint == collateral_mint.key()
     )]
@@ -61,11 +62,19 @@ pub struct MintOptions<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
+    /// Protocol configuration
     #[account(
-        constraint = market.pyth_oracle_price == pyth_oracle_price.key()
+        seeds = [b"config"],
+        bump,
     )]
-    pub pyth_oracle_price: AccountInfo<'info>,
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
 
     /// The token account to receive the short option notes
     pub short_note_account: Box<Account<'info, TokenAccount>>,
@@ -95,6 +104,17 @@ impl<'info> MintOptions<'info> {
         )
     }
 
+    fn fee_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.deposit_account.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.depositor.to_account_info(),
+            },
+        )
+    }
+
     fn short_note_mint_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
         CpiContext::new(
             self.token_program.to_account_info(),
@@ -118,33 +138,35 @@ impl<'info> MintOptions<'info> {
     }
 }
 
-/// Deposit collateral and mint options
+/// Deposit collateral and mint options, the mint fee is taken out of the deposited collateral,
+/// no options can be written once some were exercised early
 pub fn handler(ctx: Context<MintOptions>, collateral: u64) -> ProgramResult {
-    if ctx.accounts.market.expiry_timestamp <= Clock::get()?.unix_timestamp {
-        return Err(ErrorCode::OptionExpired.into());
-    }
-
-    let oracle_data = ctx.accounts.pyth_oracle_price.try_borrow_data()?;
-
-    let oracle = match pyth_client::load_price(&oracle_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    let price = match oracle.get_current_price() {
-        None => return Err(ErrorCode::PriceError.into()),
-        Some(val) => val,
-    };
-
-    let options = calculate_option_amount(
-        collateral,
-        ctx.accounts.market.strike_price,
-        ctx.accounts.market.is_put,
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_MINT)?;
+
+    ctx.accounts
+        .market
+        .check_can_mint(Clock::get()?.unix_timestamp)?;
+
+    let fee = calculate_fee(collateral, ctx.accounts.config.mint_fee_bps, Rounding::Ceil)?;
+    let deposit = collateral
+        .checked_sub(fee)
+        .ok_or(ErrorCode::MathUnderflow)?;
+
+    // Options are rounded down so burning them never returns more than was deposited
+    let options = ctx.accounts.market.option_amount(
+        deposit,
         ctx.accounts.collateral_mint.decimals,
         ctx.accounts.base_mint.decimals,
-        price.expo,
-    );
+        Rounding::Floor,
+    )?;
 
-    token::transfer(ctx.accounts.transfer_context(), collateral)?;
+    token::transfer(ctx.accounts.transfer_context(), deposit)?;
+
+    if fee > 0 {
+        token::transfer(ctx.accounts.fee_context(), fee)?;
+    }
 
     let market = ctx.accounts.market.key();
     let seeds = &[
@@ -165,6 +187,7 @@ pub fn handler(ctx: Context<MintOptions>, collateral: u64) -> ProgramResult {
 
     emit!(MintEvent {
         collateral,
+        fee,
         options,
         market: ctx.accounts.market.key(),
         depositor: ctx.accounts.depositor.key(),
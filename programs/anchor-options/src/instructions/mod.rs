@@ -1,13 +1,57 @@
+pub mod accept_admin;
+pub mod add_strike;
 pub mod burn;
+pub mod check_invariants;
+pub mod close_market;
+pub mod close_position;
+pub mod collect_fees;
+pub mod create_schedule;
+pub mod create_series;
+pub mod exercise;
+pub mod exercise_physical;
+pub mod init_config;
+pub mod init_fee_vault;
+pub mod init_manual_price;
 pub mod init_option;
 pub mod mint;
+pub mod override_settlement_price;
+pub mod post_manual_price;
+pub mod record_price_sample;
 pub mod redeem;
+pub mod report_barrier_breach;
+pub mod roll_schedule;
+pub mod set_global_pause;
+pub mod set_market_pause;
 pub mod settle;
+pub mod settle_on_spot;
+pub mod update_config;
 pub mod withdraw;
 
+pub use accept_admin::*;
+pub use add_strike::*;
 pub use burn::*;
+pub use check_invariants::*;
+pub use close_market::*;
+pub use close_position::*;
+pub use collect_fees::*;
+pub use create_schedule::*;
+pub use create_series::*;
+pub use exercise::*;
+pub use exercise_physical::*;
+pub use init_config::*;
+pub use init_fee_vault::*;
+pub use init_manual_price::*;
 pub use init_option::*;
 pub use mint::*;
+pub use override_settlement_price::*;
+pub use post_manual_price::*;
+pub use record_price_sample::*;
 pub use redeem::*;
+pub use report_barrier_breach::*;
+pub use roll_schedule::*;
+pub use set_global_pause::*;
+pub use set_market_pause::*;
 pub use settle::*;
+pub use settle_on_spot::*;
+pub use update_config::*;
 pub use withdraw::*;
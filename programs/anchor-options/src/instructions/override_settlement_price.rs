@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct SettlementOverridden {
+    market: Pubkey,
+    guardian: Pubkey,
+    original_expiry_price: u64,
+    previous_expiry_price: u64,
+    expiry_price: u64,
+    justification_hash: [u8; 32],
+}
+
+#[derive(Accounts)]
+#[instruction(expiry_price: u64, justification_hash: [u8; 32])]
+pub struct OverrideSettlementPrice<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = guardian,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The guardian of the protocol
+    pub guardian: Signer<'info>,
+}
+
+/// Replace the expiry price of a settled option during its dispute window and recalculate the payout owed to the holders,
+/// the justification hash commits to the off-chain evidence for the new price
+pub fn handler(
+    ctx: Context<OverrideSettlementPrice>,
+    expiry_price: u64,
+    justification_hash: [u8; 32],
+) -> ProgramResult {
+    let market = &mut ctx.accounts.market;
+
+    if market.expiry_price == 0 {
+        return Err(ErrorCode::OptionNotSettled.into());
+    }
+
+    if market.dispute_end_timestamp <= Clock::get()?.unix_timestamp {
+        return Err(ErrorCode::DisputeWindowClosed.into());
+    }
+
+    if expiry_price == 0 {
+        return Err(ErrorCode::PriceError.into());
+    }
+
+    let previous_expiry_price = market.expiry_price;
+    market.expiry_price = expiry_price;
+    market.update_settled_payout(
+        ctx.accounts.collateral_mint.decimals,
+        ctx.accounts.base_mint.decimals,
+    )?;
+
+    emit!(SettlementOverridden {
+        expiry_price,
+        previous_expiry_price,
+        justification_hash,
+        original_expiry_price: market.original_expiry_price,
+        market: market.key(),
+        guardian: ctx.accounts.guardian.key(),
+    });
+
+    Ok(())
+}
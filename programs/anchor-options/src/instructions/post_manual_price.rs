@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[event]
+pub struct ManualPriceEvent {
+    manual_price: Pubkey,
+    price: i64,
+    conf: u64,
+    expo: i32,
+    publish_slot: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(price: i64, conf: u64)]
+pub struct PostManualPrice<'info> {
+    /// Price account
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub manual_price: Box<Account<'info, ManualPrice>>,
+
+    /// The key allowed to post prices
+    pub authority: Signer<'info>,
+}
+
+/// Post a new price to an admin-posted price account
+pub fn handler(ctx: Context<PostManualPrice>, price: i64, conf: u64) -> ProgramResult {
+    let publish_slot = Clock::get()?.slot;
+
+    ctx.accounts.manual_price.price = price;
+    ctx.accounts.manual_price.conf = conf;
+    ctx.accounts.manual_price.publish_slot = publish_slot;
+
+    emit!(ManualPriceEvent {
+        price,
+        conf,
+        publish_slot,
+        manual_price: ctx.accounts.manual_price.key(),
+        expo: ctx.accounts.manual_price.expo,
+    });
+
+    Ok(())
+}
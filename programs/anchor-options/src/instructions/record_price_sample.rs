@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct PriceSampleEvent {
+    market: Pubkey,
+    price: u64,
+    timestamp: i64,
+    num_samples: u32,
+}
+
+#[derive(Accounts)]
+pub struct RecordPriceSample<'info> {
+    /// Option account
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        mut,
+        constraint = market.settlement == settlement.key()
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+}
+
+/// Record an oracle price sample during the settlement window of an option
+pub fn handler(ctx: Context<RecordPriceSample>) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_SETTLE)?;
+
+    let clock = Clock::get()?;
+    let market = &ctx.accounts.market;
+
+    if market.expiry_timestamp < clock.unix_timestamp {
+        return Err(ErrorCode::OptionExpired.into());
+    }
+
+    let window_start_timestamp = market
+        .expiry_timestamp
+        .checked_sub(market.settlement_window)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if market.settlement_window == 0 || window_start_timestamp > clock.unix_timestamp {
+        return Err(ErrorCode::SettlementWindowNotOpen.into());
+    }
+
+    let price = market
+        .oracle_kind
+        .load_price(&ctx.accounts.oracle_price, market.price_exponent)?;
+
+    if price.price < 0 {
+        return Err(ErrorCode::PriceError.into());
+    }
+
+    price.check_guards(
+        clock.slot,
+        market.max_staleness_slots,
+        market.max_confidence_bps,
+    )?;
+
+    let settlement = &mut ctx.accounts.settlement;
+
+    if settlement.num_samples == 0 {
+        settlement.first_sample_timestamp = clock.unix_timestamp;
+    } else {
+        let elapsed = clock
+            .unix_timestamp
+            .checked_sub(settlement.last_sample_timestamp)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if elapsed < market.min_sample_spacing.max(1) {
+            return Err(ErrorCode::PriceSampleTooEarly.into());
+        }
+
+        let elapsed = elapsed as u128;
+        settlement.cumulative_price = (settlement.last_sample_price as u128)
+            .checked_mul(elapsed)
+            .and_then(|weighted| settlement.cumulative_price.checked_add(weighted))
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+
+    settlement.num_samples = settlement
+        .num_samples
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    settlement.price_sum = settlement
+        .price_sum
+        .checked_add(price.price as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    settlement.last_sample_timestamp = clock.unix_timestamp;
+    settlement.last_sample_price = price.price as u64;
+
+    emit!(PriceSampleEvent {
+        market: ctx.accounts.market.key(),
+        price: price.price as u64,
+        timestamp: clock.unix_timestamp,
+        num_samples: settlement.num_samples,
+    });
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use anchor_spl::{
 };
 
 use crate::errors::ErrorCode;
-use crate::instructions::settle::SettleEvent;
+use crate::instructions::settle::settle_market;
 use crate::math::*;
 use crate::state::*;
 
@@ -16,6 +16,7 @@ pub struct RedeemEvent {
     redeem_account: Pubkey,
     long_note_account: Pubkey,
     payout: u64,
+    fee: u64,
     options: u64,
 }
 
@@ -23,6 +24,7 @@ pub struct RedeemEvent {
 #[instruction(options: u64)]
 pub struct RedeemOptions<'info> {
     /// Option account
+    #[account(mut)]
     pub market: Box<Account<'info, OptionMarket>>,
 
     /// PDA which has authority over all assets in the market
@@ -37,12 +39,18 @@ pub struct RedeemOptions<'info> {
     )]
     pub base_mint: Box<Account<'info, Mint>>,
 
-    /// Mint account for the collateral token (should be same as base_mint if the option is a call)
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
     #[account(
         constraint = market.collateral_mint == collateral_mint.key()
     )]
     pub collateral_mint: Box<Account<'info, Mint>>,
 
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
     /// Mint account for notes that represent a long option
     #[account(
         constraint = market.long_note_mint == long_note_mint.key()
@@ -55,11 +63,31 @@ pub struct RedeemOptions<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Account accumulating the price samples used to settle the option
     #[account(
-        constraint = market.pyth_oracle_price == pyth_oracle_price.key()
+        constraint = market.settlement == settlement.key()
     )]
-    pub pyth_oracle_price: AccountInfo<'info>,
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
 
     /// The token account to burn the long option notes
     #[account(
@@ -89,6 +117,17 @@ impl<'info> RedeemOptions<'info> {
         )
     }
 
+    fn fee_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
     fn long_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
         CpiContext::new(
             self.token_program.to_account_info(),
@@ -101,49 +140,46 @@ impl<'info> RedeemOptions<'info> {
     }
 }
 
-/// Claim profits from an option after expiry
+/// Claim profits from an option after expiry, the redeem fee is taken out of the payout
 pub fn handler(ctx: Context<RedeemOptions>, options: u64) -> ProgramResult {
-    if ctx.accounts.market.expiry_timestamp > Clock::get()?.unix_timestamp {
-        return Err(ErrorCode::OptionNotExpired.into());
-    }
-
-    let oracle_data = ctx.accounts.pyth_oracle_price.try_borrow_data()?;
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_REDEEM)?;
 
-    let oracle = match pyth_client::load_price(&oracle_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    let price = match oracle.get_current_price() {
-        None => return Err(ErrorCode::PriceError.into()),
-        Some(val) => val,
-    };
+    if ctx.accounts.market.settlement_style != SettlementStyle::Cash {
+        return Err(ErrorCode::WrongSettlementStyle.into());
+    }
 
-    if ctx.accounts.market.expiry_price == 0 {
-        if price.price < 0 {
-            return Err(ErrorCode::PriceError.into());
-        }
+    let now = Clock::get()?.unix_timestamp;
+    if ctx.accounts.market.expiry_timestamp > now {
+        return Err(ErrorCode::OptionNotExpired.into());
+    }
 
-        ctx.accounts.market.expiry_price = price.price as u64;
+    settle_market(
+        &mut ctx.accounts.market,
+        &ctx.accounts.settlement,
+        &ctx.accounts.oracle_price,
+        ctx.remaining_accounts,
+        &ctx.accounts.config,
+        &ctx.accounts.vault,
+        &ctx.accounts.short_note_mint,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.base_mint,
+    )?;
 
-        emit!(SettleEvent {
-            market: ctx.accounts.market.key(),
-            expiry_price: price.price as u64,
-        });
+    if ctx.accounts.market.dispute_end_timestamp > now {
+        return Err(ErrorCode::DisputeWindowOpen.into());
     }
 
-    let payout = calculate_expired_value(
+    let payout = ctx.accounts.market.expired_value(
         options,
-        ctx.accounts.market.strike_price,
         ctx.accounts.market.expiry_price,
-        ctx.accounts.market.is_put,
         ctx.accounts.collateral_mint.decimals,
         ctx.accounts.base_mint.decimals,
-        price.expo,
-    );
+        Rounding::Floor,
+    )?;
 
-    if payout > 0 {
-        token::transfer(ctx.accounts.transfer_context(), payout)?;
-    }
+    let fee = calculate_fee(payout, ctx.accounts.config.redeem_fee_bps, Rounding::Ceil)?;
 
     let market = ctx.accounts.market.key();
     let seeds = &[
@@ -152,6 +188,17 @@ pub fn handler(ctx: Context<RedeemOptions>, options: u64) -> ProgramResult {
         &[ctx.accounts.market.bumps.market_authority],
     ];
 
+    if payout > fee {
+        token::transfer(
+            ctx.accounts.transfer_context().with_signer(&[seeds]),
+            payout - fee,
+        )?;
+    }
+
+    if fee > 0 {
+        token::transfer(ctx.accounts.fee_context().with_signer(&[seeds]), fee)?;
+    }
+
     token::burn(
         ctx.accounts.long_note_burn_context().with_signer(&[seeds]),
         options,
@@ -159,6 +206,7 @@ pub fn handler(ctx: Context<RedeemOptions>, options: u64) -> ProgramResult {
 
     emit!(RedeemEvent {
         payout,
+        fee,
         options,
         market: ctx.accounts.market.key(),
         holder: ctx.accounts.holder.key(),
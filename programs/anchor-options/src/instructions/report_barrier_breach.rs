@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct BarrierBreachEvent {
+    market: Pubkey,
+    barrier_kind: BarrierKind,
+    barrier_price: u64,
+    price: u64,
+    timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct ReportBarrierBreach<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+}
+
+/// Record that the oracle price crossed the barrier of an option before expiry, knocking it in or out
+pub fn handler(ctx: Context<ReportBarrierBreach>) -> ProgramResult {
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_SETTLE)?;
+
+    let clock = Clock::get()?;
+    let market = &mut ctx.accounts.market;
+
+    if market.barrier_kind == BarrierKind::None {
+        return Err(ErrorCode::NotBarrier.into());
+    }
+
+    if market.knocked {
+        return Err(ErrorCode::BarrierAlreadyBreached.into());
+    }
+
+    // The barrier state must not change once the option can be settled on it
+    if market.expiry_timestamp <= clock.unix_timestamp || market.expiry_price != 0 {
+        return Err(ErrorCode::OptionExpired.into());
+    }
+
+    let price = market
+        .oracle_kind
+        .load_price(&ctx.accounts.oracle_price, market.price_exponent)?;
+
+    if price.price < 0 {
+        return Err(ErrorCode::PriceError.into());
+    }
+
+    price.check_guards(
+        clock.slot,
+        market.max_staleness_slots,
+        market.max_confidence_bps,
+    )?;
+
+    if !market.is_barrier_breached(price.price as u64) {
+        return Err(ErrorCode::BarrierNotBreached.into());
+    }
+
+    market.knocked = true;
+
+    emit!(BarrierBreachEvent {
+        market: market.key(),
+        barrier_kind: market.barrier_kind,
+        barrier_price: market.barrier_price,
+        price: price.price as u64,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::instructions::init_option::*;
+use crate::math::*;
+use crate::state::*;
+
+#[event]
+pub struct RollEvent {
+    schedule: Pubkey,
+    market: Pubkey,
+    crank: Pubkey,
+    expiry_timestamp: i64,
+    spot_price: u64,
+    strike_price: u64,
+    reward: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(params: OptionParams)]
+pub struct RollSchedule<'info> {
+    /// Schedule account listing the new market
+    #[account(
+        mut,
+        constraint = schedule.base_mint == option.base_mint.key(),
+        constraint = schedule.collateral_mint == option.collateral_mint.key(),
+        constraint = schedule.exercise_mint == option.exercise_mint.key(),
+        constraint = schedule.oracle_price == option.oracle_price.key(),
+    )]
+    pub schedule: Box<Account<'info, Schedule>>,
+
+    /// Accounts of the new market, the payer is the crank caller
+    pub option: InitializeOption<'info>,
+}
+
+/// List the next market of a schedule, moving on to the next expiry once the current one passed,
+/// the parameters must be those of the schedule with the expected expiry and strike
+pub fn handler(ctx: Context<RollSchedule>, params: OptionParams) -> ProgramResult {
+    let clock = Clock::get()?;
+    let schedule = &mut ctx.accounts.schedule;
+
+    if schedule.params.expiry_timestamp <= clock.unix_timestamp {
+        let periods = clock
+            .unix_timestamp
+            .checked_sub(schedule.params.expiry_timestamp)
+            .map(|elapsed| elapsed / schedule.cadence + 1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        schedule.params.expiry_timestamp = periods
+            .checked_mul(schedule.cadence)
+            .and_then(|rolled| schedule.params.expiry_timestamp.checked_add(rolled))
+            .ok_or(ErrorCode::MathOverflow)?;
+        schedule.spot_price = 0;
+        schedule.num_listed = 0;
+    } else if schedule.num_listed as usize >= schedule.strike_bps.len() {
+        return Err(ErrorCode::ScheduleNotDue.into());
+    }
+
+    if schedule.spot_price == 0 {
+        let price = schedule.params.oracle_kind.load_price(
+            &ctx.accounts.option.oracle_price,
+            schedule.params.price_exponent,
+        )?;
+
+        if price.price <= 0 {
+            return Err(ErrorCode::PriceError.into());
+        }
+
+        price.check_guards(
+            clock.slot,
+            schedule.params.max_staleness_slots,
+            schedule.params.max_confidence_bps,
+        )?;
+
+        schedule.spot_price = price.price as u64;
+    }
+
+    let strike_price = calculate_relative_strike(
+        schedule.spot_price,
+        schedule.strike_bps[schedule.num_listed as usize],
+        schedule.strike_increment,
+    )?;
+
+    let expected = OptionParams {
+        strike_price,
+        ..schedule.params.clone()
+    };
+    if params != expected {
+        return Err(ErrorCode::InvalidSeriesParams.into());
+    }
+
+    init_market(
+        &mut ctx.accounts.option,
+        &ctx.bumps,
+        &params,
+        ctx.remaining_accounts,
+    )?;
+
+    schedule.num_listed = schedule
+        .num_listed
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Pay the crank out of the lamports deposited above the rent exemption of the schedule
+    let schedule_info = schedule.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(schedule_info.data_len());
+    let reward = schedule
+        .crank_reward
+        .min(schedule_info.lamports().saturating_sub(rent_exempt));
+
+    if reward > 0 {
+        let crank_info = ctx.accounts.option.payer.to_account_info();
+        **schedule_info.try_borrow_mut_lamports()? -= reward;
+        **crank_info.try_borrow_mut_lamports()? += reward;
+    }
+
+    emit!(RollEvent {
+        strike_price,
+        reward,
+        expiry_timestamp: params.expiry_timestamp,
+        spot_price: schedule.spot_price,
+        schedule: schedule.key(),
+        market: ctx.accounts.option.market.key(),
+        crank: ctx.accounts.option.payer.key(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct GlobalPauseEvent {
+    config: Pubkey,
+    authority: Pubkey,
+    paused: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(paused: u8)]
+pub struct SetGlobalPause<'info> {
+    /// Protocol configuration
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The admin or guardian of the protocol
+    pub authority: Signer<'info>,
+}
+
+/// Set the operations paused for every market, the guardian can only pause more operations
+pub fn handler(ctx: Context<SetGlobalPause>, paused: u8) -> ProgramResult {
+    let config = &mut ctx.accounts.config;
+
+    if paused & !PAUSE_ALL != 0 {
+        return Err(ErrorCode::InvalidPauseFlags.into());
+    }
+
+    if !config.can_pause(&ctx.accounts.authority.key(), config.paused, paused) {
+        return Err(ErrorCode::Unauthorized.into());
+    }
+
+    config.paused = paused;
+
+    emit!(GlobalPauseEvent {
+        paused,
+        config: config.key(),
+        authority: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
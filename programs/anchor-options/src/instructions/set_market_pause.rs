@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct MarketPauseEvent {
+    market: Pubkey,
+    authority: Pubkey,
+    paused: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(paused: u8)]
+pub struct SetMarketPause<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The admin or guardian of the protocol
+    pub authority: Signer<'info>,
+}
+
+/// Set the operations paused for a market, the guardian can only pause more operations
+pub fn handler(ctx: Context<SetMarketPause>, paused: u8) -> ProgramResult {
+    let market = &mut ctx.accounts.market;
+
+    if paused & !PAUSE_ALL != 0 {
+        return Err(ErrorCode::InvalidPauseFlags.into());
+    }
+
+    if !ctx
+        .accounts
+        .config
+        .can_pause(&ctx.accounts.authority.key(), market.paused, paused)
+    {
+        return Err(ErrorCode::Unauthorized.into());
+    }
+
+    market.paused = paused;
+
+    emit!(MarketPauseEvent {
+        paused,
+        market: market.key(),
+        authority: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
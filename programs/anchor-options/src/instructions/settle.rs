@@ -1,57 +1,259 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
 
 use crate::errors::ErrorCode;
+use crate::math::*;
 use crate::state::*;
 
 #[event]
 pub struct SettleEvent {
     pub market: Pubkey,
     pub expiry_price: u64,
+    pub settlement_sources: u8,
 }
 
 #[derive(Accounts)]
 pub struct SettleOption<'info> {
     /// Option account
+    #[account(mut)]
     pub market: Box<Account<'info, OptionMarket>>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
+    /// Account accumulating the price samples used to settle the option
     #[account(
-        constraint = market.pyth_oracle_price == pyth_oracle_price.key()
+        constraint = market.settlement == settlement.key()
     )]
-    pub pyth_oracle_price: AccountInfo<'info>,
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
 }
 
-/// Settles an option by recording the expiry price
+/// Settles an option by recording the expiry price,
+/// the price accounts of every fallback source must be passed as remaining accounts
 pub fn handler(ctx: Context<SettleOption>) -> ProgramResult {
     if ctx.accounts.market.expiry_timestamp > Clock::get()?.unix_timestamp {
         return Err(ErrorCode::OptionNotExpired.into());
     }
 
-    if ctx.accounts.market.expiry_price != 0 {
+    settle_market(
+        &mut ctx.accounts.market,
+        &ctx.accounts.settlement,
+        &ctx.accounts.oracle_price,
+        ctx.remaining_accounts,
+        &ctx.accounts.config,
+        &ctx.accounts.vault,
+        &ctx.accounts.short_note_mint,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.base_mint,
+    )
+}
+
+/// Records the expiry price of an expired option if it has not been recorded yet,
+/// using the average of the settlement samples if the option has a settlement window,
+/// the arithmetic mean for Asian options and the time-weighted average otherwise,
+/// or the median of the price sources passing the oracle guards if the option settles on the spot price,
+/// and snapshots the vault the notes are paid out of
+#[allow(clippy::too_many_arguments)]
+pub fn settle_market<'info>(
+    market: &mut Account<'info, OptionMarket>,
+    settlement: &Settlement,
+    oracle_price: &AccountInfo<'info>,
+    fallback_prices: &[AccountInfo],
+    config: &ProtocolConfig,
+    vault: &TokenAccount,
+    short_note_mint: &Mint,
+    collateral_mint: &Mint,
+    base_mint: &Mint,
+) -> ProgramResult {
+    if market.expiry_price != 0 {
         return Ok(());
     }
 
-    let oracle_data = ctx.accounts.pyth_oracle_price.try_borrow_data()?;
+    config.check_not_paused(market, PAUSE_SETTLE)?;
 
-    let oracle = match pyth_client::load_price(&oracle_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    let price = match oracle.get_current_price() {
-        None => return Err(ErrorCode::PriceError.into()),
-        Some(val) => val,
+    let (expiry_price, settlement_sources) = if market.settlement_window > 0 {
+        let now = Clock::get()?.unix_timestamp;
+        let grace_end_timestamp = market.grace_end_timestamp()?;
+
+        // A window short of samples settles on those recorded once the grace period is over,
+        // a window without any can only fall back to the spot price through the guardian
+        if settlement.num_samples == 0
+            || (settlement.num_samples < market.min_settlement_samples && now < grace_end_timestamp)
+        {
+            return Err(ErrorCode::InsufficientPriceSamples.into());
+        }
+
+        // Samples are only recorded from the primary source
+        let expiry_price = if market.is_asian {
+            calculate_average_price(settlement.price_sum, settlement.num_samples)?
+        } else {
+            calculate_time_weighted_price(
+                settlement.cumulative_price,
+                settlement.last_sample_price,
+                settlement.first_sample_timestamp,
+                settlement.last_sample_timestamp,
+                market.expiry_timestamp,
+            )?
+        };
+        (expiry_price, 1)
+    } else {
+        load_spot_price(market, oracle_price, fallback_prices)?
     };
 
-    if price.price < 0 {
-        return Err(ErrorCode::PriceError.into());
+    record_expiry_price(
+        market,
+        expiry_price,
+        settlement_sources,
+        config,
+        vault,
+        short_note_mint,
+        collateral_mint,
+        base_mint,
+    )
+}
+
+/// Load the median of the price sources of an option passing the oracle guards,
+/// returned with the bitmask of the sources it was computed from
+pub fn load_spot_price(
+    market: &OptionMarket,
+    oracle_price: &AccountInfo,
+    fallback_prices: &[AccountInfo],
+) -> Result<(u64, u8), ProgramError> {
+    let slot = Clock::get()?.slot;
+
+    let mut results = vec![load_settlement_price(
+        market,
+        market.oracle_kind,
+        oracle_price,
+        slot,
+    )];
+    for source in market.fallback_sources.iter() {
+        if source.oracle_price != Pubkey::default() {
+            // Every registered source must be read, so the settler cannot pick which prices feed the median
+            let account = fallback_prices
+                .iter()
+                .find(|account| account.key() == source.oracle_price)
+                .ok_or(ErrorCode::InvalidPriceSources)?;
+            results.push(load_settlement_price(
+                market,
+                source.oracle_kind,
+                account,
+                slot,
+            ));
+        }
+    }
+
+    let num_sources = results.len();
+    let mut prices = Vec::with_capacity(num_sources);
+    let mut settlement_sources = 0;
+    let mut first_error = None;
+
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(price) => {
+                prices.push(price);
+                settlement_sources |= 1 << index;
+            }
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+
+    if prices.len() < (market.settlement_quorum.max(1) as usize) {
+        return match first_error {
+            Some(err) if num_sources == 1 => Err(err),
+            _ => Err(ErrorCode::InsufficientPriceSources.into()),
+        };
     }
 
-    ctx.accounts.market.expiry_price = price.price as u64;
+    Ok((calculate_median_price(&mut prices)?, settlement_sources))
+}
+
+/// Record the expiry price of an option, opening the dispute window and snapshotting the vault
+#[allow(clippy::too_many_arguments)]
+pub fn record_expiry_price(
+    market: &mut Account<OptionMarket>,
+    expiry_price: u64,
+    settlement_sources: u8,
+    config: &ProtocolConfig,
+    vault: &TokenAccount,
+    short_note_mint: &Mint,
+    collateral_mint: &Mint,
+    base_mint: &Mint,
+) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+
+    market.expiry_price = expiry_price;
+    market.original_expiry_price = expiry_price;
+    market.settlement_sources = settlement_sources;
+    market.dispute_end_timestamp = now
+        .checked_add(config.dispute_period)
+        .ok_or(ErrorCode::MathOverflow)?;
+    market.snapshot_settlement(
+        vault.amount,
+        short_note_mint.supply,
+        collateral_mint.decimals,
+        base_mint.decimals,
+    )?;
 
     emit!(SettleEvent {
-        market: ctx.accounts.market.key(),
-        expiry_price: price.price as u64,
+        market: market.key(),
+        expiry_price,
+        settlement_sources,
     });
 
     Ok(())
 }
+
+/// Load a price from a source of the option, rejecting prices failing the oracle guards
+fn load_settlement_price(
+    market: &OptionMarket,
+    oracle_kind: OracleKind,
+    oracle_price: &AccountInfo,
+    slot: u64,
+) -> Result<u64, ProgramError> {
+    let price = oracle_kind.load_price(oracle_price, market.price_exponent)?;
+
+    if price.price < 0 {
+        return Err(ErrorCode::PriceError.into());
+    }
+
+    price.check_guards(slot, market.max_staleness_slots, market.max_confidence_bps)?;
+
+    Ok(price.price as u64)
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+
+use crate::errors::ErrorCode;
+use crate::instructions::settle::{load_spot_price, record_expiry_price};
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct SettleOnSpot<'info> {
+    /// Option account
+    #[account(mut)]
+    pub market: Box<Account<'info, OptionMarket>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        constraint = market.settlement == settlement.key()
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
+
+    /// Protocol configuration
+    #[account(
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Mint account for the base token
+    #[account(
+        constraint = market.base_mint == base_mint.key()
+    )]
+    pub base_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for the collateral token
+    #[account(
+        constraint = market.collateral_mint == collateral_mint.key()
+    )]
+    pub collateral_mint: Box<Account<'info, Mint>>,
+
+    /// Mint account for notes that represent a short option
+    #[account(
+        constraint = market.short_note_mint == short_note_mint.key()
+    )]
+    pub short_note_mint: Box<Account<'info, Mint>>,
+
+    /// Vault with custody over the collateral tokens
+    #[account(
+        constraint = market.vault == vault.key()
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// The admin or guardian of the protocol
+    pub authority: Signer<'info>,
+}
+
+/// Settle an option whose settlement window has no samples on the spot price once the grace period is over,
+/// the price accounts of every fallback source must be passed as remaining accounts
+pub fn handler(ctx: Context<SettleOnSpot>) -> ProgramResult {
+    if !ctx
+        .accounts
+        .config
+        .is_admin_or_guardian(&ctx.accounts.authority.key())
+    {
+        return Err(ErrorCode::Unauthorized.into());
+    }
+
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_SETTLE)?;
+
+    let market = &ctx.accounts.market;
+
+    if market.expiry_price != 0 {
+        return Err(ErrorCode::OptionAlreadySettled.into());
+    }
+
+    if market.settlement_window == 0 {
+        return Err(ErrorCode::InvalidSettlementWindow.into());
+    }
+
+    // Any sample recorded is used instead, so the spot price only replaces a window keepers missed entirely
+    if ctx.accounts.settlement.num_samples > 0 {
+        return Err(ErrorCode::PriceSamplesRecorded.into());
+    }
+
+    if market.grace_end_timestamp()? > Clock::get()?.unix_timestamp {
+        return Err(ErrorCode::GracePeriodOpen.into());
+    }
+
+    let (expiry_price, settlement_sources) = load_spot_price(
+        &ctx.accounts.market,
+        &ctx.accounts.oracle_price,
+        ctx.remaining_accounts,
+    )?;
+
+    record_expiry_price(
+        &mut ctx.accounts.market,
+        expiry_price,
+        settlement_sources,
+        &ctx.accounts.config,
+        &ctx.accounts.vault,
+        &ctx.accounts.short_note_mint,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.base_mint,
+    )
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+#[event]
+pub struct ConfigEvent {
+    config: Pubkey,
+    admin: Pubkey,
+    update: ConfigUpdate,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// Protocol configuration
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = admin,
+    )]
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// The admin of the protocol
+    pub admin: Signer<'info>,
+}
+
+/// Apply a change to the protocol configuration
+pub fn handler(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> ProgramResult {
+    let config = &mut ctx.accounts.config;
+
+    match update {
+        ConfigUpdate::PendingAdmin { admin } => {
+            config.pending_admin = admin;
+        }
+        ConfigUpdate::CollateralMint { mint, allowed } => {
+            update_allow_list(
+                &mut config.collateral_mints,
+                mint,
+                allowed,
+                MAX_COLLATERAL_MINTS,
+            )?;
+        }
+        ConfigUpdate::Oracle {
+            oracle_price,
+            allowed,
+        } => {
+            update_allow_list(&mut config.oracles, oracle_price, allowed, MAX_ORACLES)?;
+        }
+        ConfigUpdate::ExpiryDistance { min, max } => {
+            if min < 0 || max < 0 || (max > 0 && max < min) {
+                return Err(ErrorCode::InvalidExpiry.into());
+            }
+
+            config.min_expiry_distance = min;
+            config.max_expiry_distance = max;
+        }
+        ConfigUpdate::Guardian { guardian } => {
+            config.guardian = guardian;
+        }
+        ConfigUpdate::DisputePeriod { dispute_period } => {
+            if dispute_period < 0 {
+                return Err(ErrorCode::InvalidDisputePeriod.into());
+            }
+
+            config.dispute_period = dispute_period;
+        }
+        ConfigUpdate::Fees {
+            mint_fee_bps,
+            redeem_fee_bps,
+            withdraw_fee_bps,
+        } => {
+            if mint_fee_bps > MAX_FEE_BPS
+                || redeem_fee_bps > MAX_FEE_BPS
+                || withdraw_fee_bps > MAX_FEE_BPS
+            {
+                return Err(ErrorCode::InvalidFee.into());
+            }
+
+            config.mint_fee_bps = mint_fee_bps;
+            config.redeem_fee_bps = redeem_fee_bps;
+            config.withdraw_fee_bps = withdraw_fee_bps;
+        }
+        ConfigUpdate::OracleGuards {
+            max_staleness_slots,
+            max_confidence_bps,
+            min_settlement_samples,
+        } => {
+            config.max_staleness_slots = max_staleness_slots;
+            config.max_confidence_bps = max_confidence_bps;
+            config.min_settlement_samples = min_settlement_samples;
+        }
+    }
+
+    emit!(ConfigEvent {
+        update,
+        config: config.key(),
+        admin: ctx.accounts.admin.key(),
+    });
+
+    Ok(())
+}
+
+fn update_allow_list(
+    list: &mut Vec<Pubkey>,
+    key: Pubkey,
+    allowed: bool,
+    max_len: usize,
+) -> ProgramResult {
+    if !allowed {
+        list.retain(|entry| *entry != key);
+    } else if !list.contains(&key) {
+        if list.len() >= max_len {
+            return Err(ErrorCode::AllowListFull.into());
+        }
+        list.push(key);
+    }
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use anchor_spl::{
 };
 
 use crate::errors::ErrorCode;
-use crate::instructions::settle::SettleEvent;
+use crate::instructions::settle::settle_market;
 use crate::math::*;
 use crate::state::*;
 
@@ -14,8 +14,11 @@ pub struct WithdrawEvent {
     market: Pubkey,
     holder: Pubkey,
     withdraw_account: Pubkey,
+    proceeds_account: Pubkey,
     short_note_account: Pubkey,
     collateral: u64,
+    fee: u64,
+    proceeds: u64,
     options: u64,
 }
 
@@ -23,6 +26,7 @@ pub struct WithdrawEvent {
 #[instruction(options: u64)]
 pub struct WithdrawCollateral<'info> {
     /// Option account
+    #[account(mut)]
     pub market: Box<Account<'info, OptionMarket>>,
 
     /// PDA which has authority over all assets in the market
@@ -37,7 +41,7 @@ pub struct WithdrawCollateral<'info> {
     )]
     pub base_mint: Box<Account<'info, Mint>>,
 
-    /// Mint account for the collateral token (should be same as base_mint if the option is a call)
+    /// Mint account for the collateral token (same as base_mint for a vanilla call without a max payout)
     #[account(
         constraint = market.collateral_mint == collateral_mint.key()
     )]
@@ -55,11 +59,37 @@ pub struct WithdrawCollateral<'info> {
     )]
     pub vault: Box<Account<'info, TokenAccount>>,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
+    /// Protocol configuration
     #[account(
-        constraint = market.pyth_oracle_price == pyth_oracle_price.key()
+        seeds = [b"config"],
+        bump,
     )]
-    pub pyth_oracle_price: AccountInfo<'info>,
+    pub config: Box<Account<'info, ProtocolConfig>>,
+
+    /// Vault collecting the protocol fees paid in the collateral token
+    #[account(
+        seeds = [b"fee_vault", collateral_mint.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Vault with custody over the tokens delivered on physical exercise
+    #[account(
+        constraint = market.exercise_vault == exercise_vault.key()
+    )]
+    pub exercise_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Account accumulating the price samples used to settle the option
+    #[account(
+        constraint = market.settlement == settlement.key()
+    )]
+    pub settlement: Box<Account<'info, Settlement>>,
+
+    /// The account where the oracle keeps the updated price of the token
+    #[account(
+        constraint = market.oracle_price == oracle_price.key()
+    )]
+    pub oracle_price: AccountInfo<'info>,
 
     /// The token account to burn the short option notes
     #[account(
@@ -70,6 +100,9 @@ pub struct WithdrawCollateral<'info> {
     /// The token account where to transfer withdrawn collateral to
     pub withdraw_account: Box<Account<'info, TokenAccount>>,
 
+    /// The token account where to transfer withdrawn exercise proceeds to
+    pub proceeds_account: Box<Account<'info, TokenAccount>>,
+
     /// Signer
     pub depositor: Signer<'info>,
 
@@ -89,6 +122,28 @@ impl<'info> WithdrawCollateral<'info> {
         )
     }
 
+    fn proceeds_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.exercise_vault.to_account_info(),
+                to: self.proceeds_account.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
+    fn fee_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        CpiContext::new(
+            self.token_program.to_account_info(),
+            Transfer {
+                from: self.vault.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.market_authority.to_account_info(),
+            },
+        )
+    }
+
     fn short_note_burn_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
         CpiContext::new(
             self.token_program.to_account_info(),
@@ -101,52 +156,74 @@ impl<'info> WithdrawCollateral<'info> {
     }
 }
 
-/// Withdraw collateral after expiry and incur any losses if the options expired in the money
+/// Withdraw collateral after expiry and incur any losses if the options expired in the money,
+/// or withdraw collateral and exercise proceeds after the exercise window of physically settled options,
+/// or withdraw all collateral once a knock-out was recorded
 pub fn handler(ctx: Context<WithdrawCollateral>, options: u64) -> ProgramResult {
-    if ctx.accounts.market.expiry_timestamp > Clock::get()?.unix_timestamp {
-        return Err(ErrorCode::OptionNotExpired.into());
-    }
-
-    let oracle_data = ctx.accounts.pyth_oracle_price.try_borrow_data()?;
-
-    let oracle = match pyth_client::load_price(&oracle_data) {
-        Ok(val) => val,
-        Err(_) => return Err(ErrorCode::PythError.into()),
-    };
-    let price = match oracle.get_current_price() {
-        None => return Err(ErrorCode::PriceError.into()),
-        Some(val) => val,
-    };
-
-    if ctx.accounts.market.expiry_price == 0 {
-        if price.price < 0 {
-            return Err(ErrorCode::PriceError.into());
-        }
+    ctx.accounts
+        .config
+        .check_not_paused(&ctx.accounts.market, PAUSE_WITHDRAW)?;
 
-        ctx.accounts.market.expiry_price = price.price as u64;
+    let now = Clock::get()?.unix_timestamp;
+    let knocked_out = ctx.accounts.market.is_knocked_out();
 
-        emit!(SettleEvent {
-            market: ctx.accounts.market.key(),
-            expiry_price: price.price as u64,
-        });
+    // Knocked out options are worthless, so writers can reclaim their collateral right away
+    if ctx.accounts.market.expiry_timestamp > now && !knocked_out {
+        return Err(ErrorCode::OptionNotExpired.into());
     }
 
-    let payout = calculate_expired_value(
-        options,
-        ctx.accounts.market.strike_price,
-        ctx.accounts.market.expiry_price,
-        ctx.accounts.market.is_put,
-        ctx.accounts.collateral_mint.decimals,
-        ctx.accounts.base_mint.decimals,
-        price.expo,
-    );
-
     let total_collateral = ctx.accounts.vault.amount;
     let total_options = ctx.accounts.short_note_mint.supply;
 
-    let collateral = calculate_collateral(options, total_collateral, total_options) - payout;
+    let (collateral, proceeds) = match ctx.accounts.market.settlement_style {
+        SettlementStyle::Cash => {
+            if knocked_out {
+                (
+                    calculate_collateral(
+                        options,
+                        total_collateral,
+                        total_options,
+                        Rounding::Floor,
+                    )?,
+                    0,
+                )
+            } else {
+                settle_market(
+                    &mut ctx.accounts.market,
+                    &ctx.accounts.settlement,
+                    &ctx.accounts.oracle_price,
+                    ctx.remaining_accounts,
+                    &ctx.accounts.config,
+                    &ctx.accounts.vault,
+                    &ctx.accounts.short_note_mint,
+                    &ctx.accounts.collateral_mint,
+                    &ctx.accounts.base_mint,
+                )?;
 
-    token::transfer(ctx.accounts.transfer_context(), collateral)?;
+                if ctx.accounts.market.dispute_end_timestamp > now {
+                    return Err(ErrorCode::DisputeWindowOpen.into());
+                }
+
+                // Every short note gets the same share of the vault as it was at settlement, whoever redeemed first
+                (ctx.accounts.market.settled_collateral_amount(options)?, 0)
+            }
+        }
+        SettlementStyle::Physical => {
+            if ctx.accounts.market.exercise_end_timestamp()? >= now {
+                return Err(ErrorCode::ExerciseWindowOpen.into());
+            }
+
+            (
+                calculate_collateral(options, total_collateral, total_options, Rounding::Floor)?,
+                calculate_collateral(
+                    options,
+                    ctx.accounts.exercise_vault.amount,
+                    total_options,
+                    Rounding::Floor,
+                )?,
+            )
+        }
+    };
 
     let market = ctx.accounts.market.key();
     let seeds = &[
@@ -155,6 +232,31 @@ pub fn handler(ctx: Context<WithdrawCollateral>, options: u64) -> ProgramResult
         &[ctx.accounts.market.bumps.market_authority],
     ];
 
+    let fee = calculate_fee(
+        collateral,
+        ctx.accounts.config.withdraw_fee_bps,
+        Rounding::Ceil,
+    )?;
+    let amount = collateral
+        .checked_sub(fee)
+        .ok_or(ErrorCode::MathUnderflow)?;
+
+    token::transfer(
+        ctx.accounts.transfer_context().with_signer(&[seeds]),
+        amount,
+    )?;
+
+    if fee > 0 {
+        token::transfer(ctx.accounts.fee_context().with_signer(&[seeds]), fee)?;
+    }
+
+    if proceeds > 0 {
+        token::transfer(
+            ctx.accounts.proceeds_context().with_signer(&[seeds]),
+            proceeds,
+        )?;
+    }
+
     token::burn(
         ctx.accounts.short_note_burn_context().with_signer(&[seeds]),
         options,
@@ -162,10 +264,13 @@ pub fn handler(ctx: Context<WithdrawCollateral>, options: u64) -> ProgramResult
 
     emit!(WithdrawEvent {
         collateral,
+        fee,
+        proceeds,
         options,
         market: ctx.accounts.market.key(),
         holder: ctx.accounts.depositor.key(),
         withdraw_account: ctx.accounts.withdraw_account.key(),
+        proceeds_account: ctx.accounts.proceeds_account.key(),
         short_note_account: ctx.accounts.short_note_account.key(),
     });
 
@@ -3,9 +3,11 @@ use anchor_lang::prelude::*;
 pub mod errors;
 pub mod instructions;
 pub mod math;
+pub mod oracle;
 pub mod state;
 
 use instructions::*;
+use state::{ConfigUpdate, OptionParams, ScheduleParams};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -13,38 +15,167 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub mod anchor_options {
     use super::*;
 
+    /// Initialize the protocol configuration, restricted to the upgrade authority of the program
+    pub fn init_config(
+        ctx: Context<InitializeConfig>,
+        min_expiry_distance: i64,
+        max_expiry_distance: i64,
+    ) -> ProgramResult {
+        instructions::init_config::handler(ctx, min_expiry_distance, max_expiry_distance)
+    }
+
+    /// Apply a change to the protocol configuration
+    pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> ProgramResult {
+        instructions::update_config::handler(ctx, update)
+    }
+
+    /// Accept the admin authority handed over by the current admin
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> ProgramResult {
+        instructions::accept_admin::handler(ctx)
+    }
+
+    /// Set the operations paused for every market
+    pub fn set_global_pause(ctx: Context<SetGlobalPause>, paused: u8) -> ProgramResult {
+        instructions::set_global_pause::handler(ctx, paused)
+    }
+
+    /// Set the operations paused for a market
+    pub fn set_market_pause(ctx: Context<SetMarketPause>, paused: u8) -> ProgramResult {
+        instructions::set_market_pause::handler(ctx, paused)
+    }
+
+    /// Initialize the vault collecting the protocol fees paid in a collateral token
+    pub fn init_fee_vault(ctx: Context<InitializeFeeVault>) -> ProgramResult {
+        instructions::init_fee_vault::handler(ctx)
+    }
+
+    /// Transfer protocol fees out of a fee vault
+    pub fn collect_fees(ctx: Context<CollectFees>, amount: u64) -> ProgramResult {
+        instructions::collect_fees::handler(ctx, amount)
+    }
+
     /// Initialize a new option
-    pub fn init_option(
-        ctx: Context<InitializeOption>,
-        strike_price: u64,
-        expiry_timestamp: i64,
-        is_put: bool,
+    pub fn init_option(ctx: Context<InitializeOption>, params: OptionParams) -> ProgramResult {
+        instructions::init_option::handler(ctx, params)
+    }
+
+    /// Create a series of options sharing every parameter but the strike
+    pub fn create_series(
+        ctx: Context<CreateSeries>,
+        params: OptionParams,
+        max_strikes: u16,
+    ) -> ProgramResult {
+        instructions::create_series::handler(ctx, params, max_strikes)
+    }
+
+    /// Create the market of a series at a new strike
+    pub fn add_strike(ctx: Context<AddStrike>, params: OptionParams) -> ProgramResult {
+        instructions::add_strike::handler(ctx, params)
+    }
+
+    /// Create a schedule listing options at a fixed cadence with strikes relative to spot
+    pub fn create_schedule(
+        ctx: Context<CreateSchedule>,
+        params: OptionParams,
+        schedule_params: ScheduleParams,
     ) -> ProgramResult {
-        instructions::init_option::handler(ctx, strike_price, expiry_timestamp, is_put)
+        instructions::create_schedule::handler(ctx, params, schedule_params)
+    }
+
+    /// List the next market of a schedule and reward the caller
+    pub fn roll_schedule(ctx: Context<RollSchedule>, params: OptionParams) -> ProgramResult {
+        instructions::roll_schedule::handler(ctx, params)
     }
 
-    /// Deposit collateral and mint options
+    /// Deposit collateral and mint options, the mint fee is taken out of the deposited collateral
     pub fn mint(ctx: Context<MintOptions>, collateral: u64) -> ProgramResult {
         instructions::mint::handler(ctx, collateral)
     }
 
-    /// Burn long and short options to withdraw collateral before expiry
+    /// Burn long and short options to withdraw collateral before expiry,
+    /// less the share of the short notes in the payouts of options exercised early
     pub fn burn(ctx: Context<BurnOptions>, options: u64) -> ProgramResult {
         instructions::burn::handler(ctx, options)
     }
 
+    /// Record an oracle price sample during the settlement window of an option
+    pub fn record_price_sample(ctx: Context<RecordPriceSample>) -> ProgramResult {
+        instructions::record_price_sample::handler(ctx)
+    }
+
     /// Settles an option by recording the expiry price
     pub fn settle(ctx: Context<SettleOption>) -> ProgramResult {
         instructions::settle::handler(ctx)
     }
 
-    /// Claim profits from an option after expiry
+    /// Settle an option whose settlement window has no samples on the spot price after the grace period,
+    /// restricted to the admin and the guardian
+    pub fn settle_on_spot(ctx: Context<SettleOnSpot>) -> ProgramResult {
+        instructions::settle_on_spot::handler(ctx)
+    }
+
+    /// Record that the oracle price crossed the barrier of an option, knocking it in or out
+    pub fn report_barrier_breach(ctx: Context<ReportBarrierBreach>) -> ProgramResult {
+        instructions::report_barrier_breach::handler(ctx)
+    }
+
+    /// Report whether the vaults of an option hold enough collateral and exercise proceeds for its outstanding notes
+    pub fn check_invariants(ctx: Context<CheckInvariants>) -> ProgramResult {
+        instructions::check_invariants::handler(ctx)
+    }
+
+    /// Replace the expiry price of a settled option during its dispute window
+    pub fn override_settlement_price(
+        ctx: Context<OverrideSettlementPrice>,
+        expiry_price: u64,
+        justification_hash: [u8; 32],
+    ) -> ProgramResult {
+        instructions::override_settlement_price::handler(ctx, expiry_price, justification_hash)
+    }
+
+    /// Claim profits from an option after expiry, the redeem fee is taken out of the payout
     pub fn redeem(ctx: Context<RedeemOptions>, options: u64) -> ProgramResult {
         instructions::redeem::handler(ctx, options)
     }
 
-    /// Withdraw collateral after expiry and incur any losses if the options expired in the money
+    /// Withdraw collateral after expiry and incur any losses if the options expired in the money,
+    /// or withdraw collateral and exercise proceeds after the exercise window of physically settled options,
+    /// or withdraw all collateral once a knock-out was recorded
     pub fn withdraw(ctx: Context<WithdrawCollateral>, options: u64) -> ProgramResult {
         instructions::withdraw::handler(ctx, options)
     }
+
+    /// Redeem long notes and withdraw the collateral of as many short notes after expiry in one transfer to the holder
+    pub fn close_position(ctx: Context<ClosePosition>, options: u64) -> ProgramResult {
+        instructions::close_position::handler(ctx, options)
+    }
+
+    /// Exercise American options before expiry at the current oracle price, the redeem fee is taken out of the payout
+    pub fn exercise(ctx: Context<ExerciseOptions>, options: u64) -> ProgramResult {
+        instructions::exercise::handler(ctx, options)
+    }
+
+    /// Exercise physically settled options by exchanging the strike for the base token
+    pub fn exercise_physical(ctx: Context<ExercisePhysical>, options: u64) -> ProgramResult {
+        instructions::exercise_physical::handler(ctx, options)
+    }
+
+    /// Close an expired option once all notes are burned, sweeping the rounding dust to the fee vault
+    pub fn close_market(ctx: Context<CloseMarket>) -> ProgramResult {
+        instructions::close_market::handler(ctx)
+    }
+
+    /// Initialize an admin-posted price account for assets without an oracle feed
+    pub fn init_manual_price(ctx: Context<InitializeManualPrice>, expo: i32) -> ProgramResult {
+        instructions::init_manual_price::handler(ctx, expo)
+    }
+
+    /// Post a new price to an admin-posted price account
+    pub fn post_manual_price(
+        ctx: Context<PostManualPrice>,
+        price: i64,
+        conf: u64,
+    ) -> ProgramResult {
+        instructions::post_manual_price::handler(ctx, price, conf)
+    }
 }
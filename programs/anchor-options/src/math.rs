@@ -1,87 +1,513 @@
-/// Calculate the amount of options mintable given the margin
+use std::convert::TryFrom;
+
+use crate::errors::ErrorCode;
+use crate::state::{OptionMarket, SettlementStyle};
+
+/// Direction in which a division that does not come out even is rounded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round toward zero
+    Floor,
+
+    /// Round away from zero
+    Ceil,
+}
+
+/// Calculate the amount of options mintable given the margin,
+/// calls with a max payout are collateralized in the quote token like puts
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_option_amount(
     collateral: u64,
     strike_price: u64,
     is_put: bool,
+    max_payout: u64,
     collateral_decimals: u8,
     base_decimals: u8,
-    pyth_exponent: i32,
-) -> u64 {
-    if is_put {
-        // options = collateral / strike
-        let decimals = (base_decimals as i32) + pyth_exponent.abs() - (collateral_decimals as i32);
-        let units = 10_u128.pow(decimals.abs() as u32);
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    if is_put || max_payout > 0 {
+        // options = collateral / strike for puts, collateral / max_payout for quote-collateralized calls
+        let price = if is_put { strike_price } else { max_payout };
+        let decimals = (base_decimals as i32) + price_exponent.abs() - (collateral_decimals as i32);
+        let units = pow10(decimals.unsigned_abs())?;
         if decimals >= 0 {
-            (((collateral as u128) * units) / (strike_price as u128)) as u64
+            to_u64(div(
+                mul(collateral as u128, units)?,
+                price as u128,
+                rounding,
+            )?)
         } else {
-            (((collateral as u128) / (strike_price as u128)) / units) as u64
+            to_u64(div(
+                collateral as u128,
+                mul(price as u128, units)?,
+                rounding,
+            )?)
         }
     } else {
-        collateral
+        Ok(collateral)
     }
 }
 
 /// Calculate the amount of margin required to mint options
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_collateral_amount(
     options: u64,
     strike_price: u64,
     is_put: bool,
+    max_payout: u64,
     collateral_decimals: u8,
     base_decimals: u8,
-    pyth_exponent: i32,
-) -> u64 {
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
     if is_put {
         // collateral = options * strike
-        let decimals = (base_decimals as i32) + pyth_exponent.abs() - (collateral_decimals as i32);
-        let units = 10_u128.pow(decimals.abs() as u32);
-        if decimals >= 0 {
-            (((options as u128) * (strike_price as u128)) / units) as u64
-        } else {
-            (((options as u128) * units) / (strike_price as u128)) as u64
-        }
+        calculate_strike_amount(
+            options,
+            strike_price,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
+    } else if max_payout > 0 {
+        // collateral = options * max_payout
+        calculate_strike_amount(
+            options,
+            max_payout,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
     } else {
-        options
+        Ok(options)
     }
 }
 
 /// Calculate the payout of an expired option
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_expired_value(
     options: u64,
     strike_price: u64,
     expiry_price: u64,
     is_put: bool,
+    max_payout: u64,
     collateral_decimals: u8,
     base_decimals: u8,
-    pyth_exponent: i32,
-) -> u64 {
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
     if is_put && (strike_price > expiry_price) {
         // payout = (strike_price - expiry_price) * options
-        let decimals = (base_decimals as i32) + pyth_exponent.abs() - (collateral_decimals as i32);
-        let payout = ((strike_price - expiry_price) as u128) * (options as u128);
-        let units = 10_u128.pow(decimals.abs() as u32);
-        if decimals >= 0 {
-            (payout / units) as u64
-        } else {
-            (payout * units) as u64
-        }
+        calculate_strike_amount(
+            options,
+            sub(strike_price, expiry_price)?,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
+    } else if !is_put && max_payout > 0 && (expiry_price > strike_price) {
+        // payout = min(expiry_price - strike_price, max_payout) * options
+        calculate_strike_amount(
+            options,
+            sub(expiry_price, strike_price)?.min(max_payout),
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
     } else if !is_put && (expiry_price > strike_price) {
         // payout = ((expiry_price - strike_price) * options) / expiry_price
-        (((expiry_price - strike_price) as u128) * (options as u128) / (expiry_price as u128))
-            as u64
+        let payout = mul(sub(expiry_price, strike_price)? as u128, options as u128)?;
+        to_u64(div(payout, expiry_price as u128, rounding)?)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Calculate the amount of spread options mintable given the margin
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_spread_option_amount(
+    collateral: u64,
+    strike_price: u64,
+    cap_strike: u64,
+    is_put: bool,
+    collateral_decimals: u8,
+    base_decimals: u8,
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    if is_put {
+        // options = collateral / (strike - cap_strike)
+        calculate_option_amount(
+            collateral,
+            sub(strike_price, cap_strike)?,
+            true,
+            0,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
+    } else {
+        // options = (collateral * cap_strike) / (cap_strike - strike)
+        let options = mul(collateral as u128, cap_strike as u128)?;
+        to_u64(div(
+            options,
+            sub(cap_strike, strike_price)? as u128,
+            rounding,
+        )?)
+    }
+}
+
+/// Calculate the amount of margin required to mint spread options, which is the maximum payout
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_spread_collateral_amount(
+    options: u64,
+    strike_price: u64,
+    cap_strike: u64,
+    is_put: bool,
+    collateral_decimals: u8,
+    base_decimals: u8,
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    if is_put {
+        // collateral = options * (strike - cap_strike)
+        calculate_strike_amount(
+            options,
+            sub(strike_price, cap_strike)?,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
+    } else {
+        // collateral = (options * (cap_strike - strike)) / cap_strike
+        let collateral = mul(options as u128, sub(cap_strike, strike_price)? as u128)?;
+        to_u64(div(collateral, cap_strike as u128, rounding)?)
+    }
+}
+
+/// Calculate the payout of an expired spread option, capped at the spread width
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_spread_expired_value(
+    options: u64,
+    strike_price: u64,
+    cap_strike: u64,
+    expiry_price: u64,
+    is_put: bool,
+    collateral_decimals: u8,
+    base_decimals: u8,
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    if is_put {
+        // payout = (strike_price - max(expiry_price, cap_strike)) * options
+        calculate_expired_value(
+            options,
+            strike_price,
+            expiry_price.max(cap_strike),
+            true,
+            0,
+            collateral_decimals,
+            base_decimals,
+            price_exponent,
+            rounding,
+        )
+    } else if expiry_price > strike_price {
+        // payout = ((min(expiry_price, cap_strike) - strike_price) * options) / expiry_price
+        let payout = mul(
+            sub(expiry_price.min(cap_strike), strike_price)? as u128,
+            options as u128,
+        )?;
+        to_u64(div(payout, expiry_price as u128, rounding)?)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Calculate the amount of binary options mintable given the margin,
+/// the payout is the amount of collateral paid per whole option
+pub fn calculate_binary_option_amount(
+    collateral: u64,
+    payout: u64,
+    base_decimals: u8,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    // options = collateral / payout
+    let units = pow10(base_decimals as u32)?;
+    to_u64(div(
+        mul(collateral as u128, units)?,
+        payout as u128,
+        rounding,
+    )?)
+}
+
+/// Calculate the amount of margin required to mint binary options
+pub fn calculate_binary_collateral_amount(
+    options: u64,
+    payout: u64,
+    base_decimals: u8,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    // collateral = options * payout
+    let units = pow10(base_decimals as u32)?;
+    to_u64(div(mul(options as u128, payout as u128)?, units, rounding)?)
+}
+
+/// Calculate the payout of an expired binary option, the full payout if in the money and nothing otherwise
+pub fn calculate_binary_expired_value(
+    options: u64,
+    strike_price: u64,
+    expiry_price: u64,
+    payout: u64,
+    is_put: bool,
+    base_decimals: u8,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    if (is_put && expiry_price < strike_price) || (!is_put && expiry_price > strike_price) {
+        calculate_binary_collateral_amount(options, payout, base_decimals, rounding)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Calculate the amount of quote tokens exchanged for the base tokens of physically exercised options
+pub fn calculate_strike_amount(
+    options: u64,
+    strike_price: u64,
+    quote_decimals: u8,
+    base_decimals: u8,
+    price_exponent: i32,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
+    // quote = options * strike
+    let decimals = (base_decimals as i32) + price_exponent.abs() - (quote_decimals as i32);
+    let units = pow10(decimals.unsigned_abs())?;
+    let quote = mul(options as u128, strike_price as u128)?;
+    if decimals >= 0 {
+        to_u64(div(quote, units, rounding)?)
     } else {
-        0
+        to_u64(mul(quote, units)?)
     }
 }
 
 /// Calculate the amount of underlying collateral for an option
-pub fn calculate_collateral(options: u64, total_collateral: u64, total_options: u64) -> u64 {
+pub fn calculate_collateral(
+    options: u64,
+    total_collateral: u64,
+    total_options: u64,
+    rounding: Rounding,
+) -> Result<u64, ErrorCode> {
     // collateral = (options * total_collateral) / total_options
-    (((options as u128) * (total_collateral as u128)) / (total_options as u128)) as u64
+    let collateral = mul(options as u128, total_collateral as u128)?;
+    to_u64(div(collateral, total_options as u128, rounding)?)
+}
+
+/// Calculate the time-weighted average price of the samples recorded until the end of the window
+pub fn calculate_time_weighted_price(
+    cumulative_price: u128,
+    last_sample_price: u64,
+    first_sample_timestamp: i64,
+    last_sample_timestamp: i64,
+    end_timestamp: i64,
+) -> Result<u64, ErrorCode> {
+    if end_timestamp <= first_sample_timestamp {
+        return Ok(last_sample_price);
+    }
+
+    // price = (cumulative_price + last_sample_price * (end - last_sample)) / (end - first_sample)
+    let last_duration = duration(last_sample_timestamp, end_timestamp)?;
+    let cumulative_price = cumulative_price
+        .checked_add(mul(last_sample_price as u128, last_duration)?)
+        .ok_or(ErrorCode::MathOverflow)?;
+    to_u64(div(
+        cumulative_price,
+        duration(first_sample_timestamp, end_timestamp)?,
+        Rounding::Floor,
+    )?)
+}
+
+/// Calculate the arithmetic mean of the sampled prices
+pub fn calculate_average_price(price_sum: u128, num_samples: u32) -> Result<u64, ErrorCode> {
+    to_u64(div(price_sum, num_samples as u128, Rounding::Floor)?)
+}
+
+/// Calculate a strike relative to the spot price, rounded to the nearest strike increment
+pub fn calculate_relative_strike(
+    spot_price: u64,
+    strike_bps: u32,
+    strike_increment: u64,
+) -> Result<u64, ErrorCode> {
+    // strike = spot_price * strike_bps / 10000
+    let strike = to_u64(div(
+        mul(spot_price as u128, strike_bps as u128)?,
+        10000,
+        Rounding::Floor,
+    )?)?;
+    if strike_increment == 0 {
+        return Ok(strike);
+    }
+
+    let rounded = (strike as u128) + (strike_increment as u128) / 2;
+    let rounded = rounded / (strike_increment as u128) * (strike_increment as u128);
+    Ok(to_u64(rounded)?.max(strike_increment))
+}
+
+/// Calculate the fee charged on an amount
+pub fn calculate_fee(amount: u64, fee_bps: u16, rounding: Rounding) -> Result<u64, ErrorCode> {
+    // fee = amount * fee_bps / 10000
+    to_u64(div(mul(amount as u128, fee_bps as u128)?, 10000, rounding)?)
+}
+
+/// Calculate the collateral and exercise proceeds the vaults of an option must hold for its outstanding notes,
+/// returned as (required_collateral, required_proceeds, is_settled)
+pub fn calculate_required_collateral(
+    market: &OptionMarket,
+    long_supply: u64,
+    short_supply: u64,
+    now: i64,
+    collateral_decimals: u8,
+    exercise_decimals: u8,
+    base_decimals: u8,
+) -> Result<(u64, u64, bool), ErrorCode> {
+    match market.settlement_style {
+        SettlementStyle::Cash if market.expiry_price != 0 && !market.is_knocked_out() => {
+            // Holders are owed the payout of their notes and writers their share of the snapshot
+            let long_payout = market.expired_value(
+                long_supply,
+                market.expiry_price,
+                collateral_decimals,
+                base_decimals,
+                Rounding::Floor,
+            )?;
+            let short_collateral = market.settled_collateral_amount(short_supply)?;
+
+            let required_collateral = long_payout
+                .checked_add(short_collateral)
+                .ok_or(ErrorCode::MathOverflow)?;
+            Ok((required_collateral, 0, true))
+        }
+        SettlementStyle::Cash => {
+            // Options exercised early already paid out of the vault and no longer need to be backed,
+            // knocked out options are worthless and the writers are owed the collateral backing the rest
+            let short_options = if market.is_knocked_out() {
+                short_supply.saturating_sub(market.exercised_options)
+            } else {
+                sub(short_supply, market.exercised_options)?
+            };
+            let required_collateral = market.collateral_amount(
+                short_options,
+                collateral_decimals,
+                base_decimals,
+                Rounding::Floor,
+            )?;
+            Ok((required_collateral, 0, market.is_knocked_out()))
+        }
+        SettlementStyle::Physical => {
+            let is_settled = market.exercise_end_timestamp()? < now || market.is_knocked_out();
+
+            // Minting and burning stop once an option is exercised, so the notes written are those outstanding
+            // plus those exercised, and every short note has the same claim on the collateral left for the
+            // unexercised notes and on the tokens delivered for the exercised ones
+            let written_options = long_supply
+                .checked_add(market.exercised_options)
+                .ok_or(ErrorCode::MathOverflow)?;
+            if written_options == 0 {
+                return Ok((0, 0, is_settled));
+            }
+
+            let unexercised_collateral = market.collateral_amount(
+                long_supply,
+                collateral_decimals,
+                base_decimals,
+                Rounding::Floor,
+            )?;
+            let delivered = if market.is_put {
+                market.exercised_options
+            } else {
+                calculate_strike_amount(
+                    market.exercised_options,
+                    market.strike_price,
+                    exercise_decimals,
+                    base_decimals,
+                    market.price_exponent,
+                    Rounding::Floor,
+                )?
+            };
+
+            Ok((
+                calculate_collateral(
+                    short_supply,
+                    unexercised_collateral,
+                    written_options,
+                    Rounding::Floor,
+                )?,
+                calculate_collateral(short_supply, delivered, written_options, Rounding::Floor)?,
+                is_settled,
+            ))
+        }
+    }
+}
+
+/// Calculate the median of the prices, the mean of the two middle prices for an even count
+pub fn calculate_median_price(prices: &mut [u64]) -> Result<u64, ErrorCode> {
+    if prices.is_empty() {
+        return Err(ErrorCode::DivideByZero);
+    }
+
+    prices.sort_unstable();
+
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        Ok(prices[middle])
+    } else {
+        to_u64(((prices[middle - 1] as u128) + (prices[middle] as u128)) / 2)
+    }
+}
+
+/// Convert an intermediate result back to a token amount or price
+fn to_u64(value: u128) -> Result<u64, ErrorCode> {
+    u64::try_from(value).map_err(|_| ErrorCode::MathOverflow)
+}
+
+fn mul(lhs: u128, rhs: u128) -> Result<u128, ErrorCode> {
+    lhs.checked_mul(rhs).ok_or(ErrorCode::MathOverflow)
+}
+
+fn div(lhs: u128, rhs: u128, rounding: Rounding) -> Result<u128, ErrorCode> {
+    let quotient = lhs.checked_div(rhs).ok_or(ErrorCode::DivideByZero)?;
+    if rounding == Rounding::Ceil && quotient * rhs < lhs {
+        Ok(quotient + 1)
+    } else {
+        Ok(quotient)
+    }
+}
+
+fn sub(lhs: u64, rhs: u64) -> Result<u64, ErrorCode> {
+    lhs.checked_sub(rhs).ok_or(ErrorCode::MathUnderflow)
+}
+
+fn pow10(exponent: u32) -> Result<u128, ErrorCode> {
+    10_u128.checked_pow(exponent).ok_or(ErrorCode::MathOverflow)
+}
+
+/// Seconds between two timestamps, the end must not be before the start
+fn duration(start: i64, end: i64) -> Result<u128, ErrorCode> {
+    let seconds = end.checked_sub(start).ok_or(ErrorCode::MathOverflow)?;
+    u128::try_from(seconds).map_err(|_| ErrorCode::MathUnderflow)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::oracle::*;
+    use crate::state::*;
+    use anchor_lang::prelude::Pubkey;
 
     const SOL_DECIMALS: u8 = 9;
     const SRM_DECIMALS: u8 = 6;
@@ -96,60 +522,117 @@ mod tests {
             50_000000000,
             100_00000000,
             false,
+            0,
             SOL_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 50_000000000);
 
         let options = calculate_option_amount(
             5000_000000,
             100_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 50_000000000);
 
         let options = calculate_option_amount(
             10_000000,
             2_00000000,
             false,
+            0,
             SRM_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 10_000000);
 
         let options = calculate_option_amount(
             20_000000,
             2_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 10_000000);
 
         let options = calculate_option_amount(
             10_00,
             6_00000000,
             false,
+            0,
             TEST_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 10_00);
 
         let options = calculate_option_amount(
             60_000000,
             6_00000000,
             true,
+            0,
             USDC_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_00);
+
+        let options = calculate_option_amount(
+            2500_000000,
+            100_00000000,
+            false,
+            50_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 50_000000000);
+
+        let options = calculate_option_amount(
+            10_000000,
+            2_00000000,
+            false,
+            1_00000000,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_000000);
+
+        let options = calculate_option_amount(
+            20_000000,
+            6_00000000,
+            false,
+            2_00000000,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(options, 10_00);
     }
 
@@ -159,61 +642,118 @@ mod tests {
             50_000000000,
             100_00000000,
             false,
+            0,
             SOL_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 50_000000000);
 
         let collateral = calculate_collateral_amount(
             50_000000000,
             100_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 5000_000000);
 
         let collateral = calculate_collateral_amount(
             10_000000,
             2_00000000,
             false,
+            0,
             SRM_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 10_000000);
 
         let collateral = calculate_collateral_amount(
             10_000000,
             2_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 20_000000);
 
         let collateral = calculate_collateral_amount(
             10_00,
             6_00000000,
             false,
+            0,
             TEST_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 10_00);
 
         let collateral = calculate_collateral_amount(
             10_00,
             6_00000000,
             true,
+            0,
             USDC_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(collateral, 60_000000);
+
+        let collateral = calculate_collateral_amount(
+            50_000000000,
+            100_00000000,
+            false,
+            50_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 2500_000000);
+
+        let collateral = calculate_collateral_amount(
+            10_000000,
+            2_00000000,
+            false,
+            1_00000000,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 10_000000);
+
+        let collateral = calculate_collateral_amount(
+            10_00,
+            6_00000000,
+            false,
+            2_00000000,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 20_000000);
     }
 
     #[test]
@@ -223,10 +763,13 @@ mod tests {
             100_00000000,
             120_00000000,
             false,
+            0,
             SOL_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 8_333333333);
 
         let payout = calculate_expired_value(
@@ -234,10 +777,13 @@ mod tests {
             100_00000000,
             80_00000000,
             false,
+            0,
             SOL_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -245,10 +791,13 @@ mod tests {
             100_00000000,
             120_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -256,10 +805,13 @@ mod tests {
             100_00000000,
             80_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SOL_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 1000_000000);
 
         let payout = calculate_expired_value(
@@ -267,10 +819,13 @@ mod tests {
             2_00000000,
             3_00000000,
             false,
+            0,
             SRM_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 3_333333);
 
         let payout = calculate_expired_value(
@@ -278,10 +833,13 @@ mod tests {
             2_00000000,
             1_00000000,
             false,
+            0,
             SRM_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -289,10 +847,13 @@ mod tests {
             2_00000000,
             3_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -300,10 +861,13 @@ mod tests {
             2_00000000,
             1_00000000,
             true,
+            0,
             USDC_DECIMALS,
             SRM_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 10_000000);
 
         let payout = calculate_expired_value(
@@ -311,10 +875,13 @@ mod tests {
             6_00000000,
             7_00000000,
             false,
+            0,
             TEST_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 1_42);
 
         let payout = calculate_expired_value(
@@ -322,10 +889,13 @@ mod tests {
             6_00000000,
             5_00000000,
             false,
+            0,
             TEST_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -333,10 +903,13 @@ mod tests {
             6_00000000,
             7_00000000,
             true,
+            0,
             USDC_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 0);
 
         let payout = calculate_expired_value(
@@ -344,25 +917,1603 @@ mod tests {
             6_00000000,
             5_00000000,
             true,
+            0,
             USDC_DECIMALS,
             TEST_DECIMALS,
             PYTH_USD_EXPONENT,
-        );
+            Rounding::Floor,
+        )
+        .unwrap();
         assert_eq!(payout, 10_000000);
-    }
 
-    #[test]
-    fn test_calculate_collateral() {
-        let collateral = calculate_collateral(10_000000000, 10_000000000, 10_000000000);
-        assert_eq!(collateral, 10_000000000);
+        let payout = calculate_expired_value(
+            50_000000000,
+            100_00000000,
+            120_00000000,
+            false,
+            50_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 1000_000000);
 
-        let collateral = calculate_collateral(5_000000000, 10_000000000, 10_000000000);
-        assert_eq!(collateral, 5_000000000);
+        let payout = calculate_expired_value(
+            50_000000000,
+            100_00000000,
+            200_00000000,
+            false,
+            50_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 2500_000000);
 
-        let collateral = calculate_collateral(10_000000000, 6_000000000, 10_000000000);
-        assert_eq!(collateral, 6_000000000);
+        let payout = calculate_expired_value(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            false,
+            50_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 0);
 
-        let collateral = calculate_collateral(5_000000000, 6_000000000, 10_000000000);
-        assert_eq!(collateral, 3_000000000);
+        let payout = calculate_expired_value(
+            10_000000,
+            2_00000000,
+            2_50000000,
+            false,
+            1_00000000,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 5_000000);
+
+        let payout = calculate_expired_value(
+            10_00,
+            6_00000000,
+            9_00000000,
+            false,
+            2_00000000,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 20_000000);
+    }
+
+    #[test]
+    fn test_calculate_spread_option_amount() {
+        let options = calculate_spread_option_amount(
+            10_000000000,
+            100_00000000,
+            125_00000000,
+            false,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 50_000000000);
+
+        let options = calculate_spread_option_amount(
+            1000_000000,
+            100_00000000,
+            80_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 50_000000000);
+
+        let options = calculate_spread_option_amount(
+            5_000000,
+            2_00000000,
+            4_00000000,
+            false,
+            SRM_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_000000);
+
+        let options = calculate_spread_option_amount(
+            10_000000,
+            2_00000000,
+            1_00000000,
+            true,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_000000);
+
+        let options = calculate_spread_option_amount(
+            2_50,
+            6_00000000,
+            8_00000000,
+            false,
+            TEST_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_00);
+
+        let options = calculate_spread_option_amount(
+            10_000000,
+            6_00000000,
+            5_00000000,
+            true,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(options, 10_00);
+    }
+
+    #[test]
+    fn test_calculate_spread_collateral_amount() {
+        let collateral = calculate_spread_collateral_amount(
+            50_000000000,
+            100_00000000,
+            125_00000000,
+            false,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 10_000000000);
+
+        let collateral = calculate_spread_collateral_amount(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 1000_000000);
+
+        let collateral = calculate_spread_collateral_amount(
+            10_000000,
+            2_00000000,
+            3_00000000,
+            false,
+            SRM_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 3_333333);
+
+        let collateral = calculate_spread_collateral_amount(
+            10_000000,
+            2_00000000,
+            1_00000000,
+            true,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 10_000000);
+
+        let collateral = calculate_spread_collateral_amount(
+            10_00,
+            6_00000000,
+            8_00000000,
+            false,
+            TEST_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 2_50);
+
+        let collateral = calculate_spread_collateral_amount(
+            10_00,
+            6_00000000,
+            5_00000000,
+            true,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 10_000000);
+    }
+
+    #[test]
+    fn test_calculate_spread_expired_value() {
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            125_00000000,
+            120_00000000,
+            false,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 8_333333333);
+
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            125_00000000,
+            150_00000000,
+            false,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 8_333333333);
+
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            125_00000000,
+            80_00000000,
+            false,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 0);
+
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            90_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 500_000000);
+
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            50_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 1000_000000);
+
+        let payout = calculate_spread_expired_value(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            120_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 0);
+
+        let payout = calculate_spread_expired_value(
+            10_000000,
+            2_00000000,
+            3_00000000,
+            4_00000000,
+            false,
+            SRM_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 2_500000);
+
+        let payout = calculate_spread_expired_value(
+            10_000000,
+            2_00000000,
+            1_00000000,
+            50000000,
+            true,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 10_000000);
+
+        let payout = calculate_spread_expired_value(
+            10_00,
+            6_00000000,
+            8_00000000,
+            7_00000000,
+            false,
+            TEST_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 1_42);
+
+        let payout = calculate_spread_expired_value(
+            10_00,
+            6_00000000,
+            5_00000000,
+            5_50000000,
+            true,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 5_000000);
+    }
+
+    #[test]
+    fn test_calculate_binary_option_amount() {
+        let options =
+            calculate_binary_option_amount(500_000000, 10_000000, SOL_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(options, 50_000000000);
+
+        let options =
+            calculate_binary_option_amount(1_000000000, 20000000, SOL_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(options, 50_000000000);
+
+        let options =
+            calculate_binary_option_amount(25_000000, 2_500000, SRM_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(options, 10_000000);
+
+        let options =
+            calculate_binary_option_amount(3_000000, 1_500000, TEST_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(options, 2_00);
+    }
+
+    #[test]
+    fn test_calculate_binary_collateral_amount() {
+        let collateral = calculate_binary_collateral_amount(
+            50_000000000,
+            10_000000,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 500_000000);
+
+        let collateral = calculate_binary_collateral_amount(
+            50_000000000,
+            20000000,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(collateral, 1_000000000);
+
+        let collateral =
+            calculate_binary_collateral_amount(10_000000, 2_500000, SRM_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(collateral, 25_000000);
+
+        let collateral =
+            calculate_binary_collateral_amount(2_00, 1_500000, TEST_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(collateral, 3_000000);
+
+        let collateral =
+            calculate_binary_collateral_amount(1, 10_000000, SOL_DECIMALS, Rounding::Floor)
+                .unwrap();
+        assert_eq!(collateral, 0);
+    }
+
+    #[test]
+    fn test_calculate_binary_expired_value() {
+        let payout = calculate_binary_expired_value(
+            50_000000000,
+            100_00000000,
+            120_00000000,
+            10_000000,
+            false,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 500_000000);
+
+        let payout = calculate_binary_expired_value(
+            50_000000000,
+            100_00000000,
+            100_00000000,
+            10_000000,
+            false,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 0);
+
+        let payout = calculate_binary_expired_value(
+            50_000000000,
+            100_00000000,
+            80_00000000,
+            10_000000,
+            true,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 500_000000);
+
+        let payout = calculate_binary_expired_value(
+            50_000000000,
+            100_00000000,
+            120_00000000,
+            10_000000,
+            true,
+            SOL_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 0);
+
+        let payout = calculate_binary_expired_value(
+            10_000000,
+            2_00000000,
+            4_00000000,
+            2_500000,
+            false,
+            SRM_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 25_000000);
+
+        let payout = calculate_binary_expired_value(
+            2_00,
+            6_00000000,
+            5_50000000,
+            1_500000,
+            true,
+            TEST_DECIMALS,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(payout, 3_000000);
+    }
+
+    #[test]
+    fn test_calculate_strike_amount() {
+        let quote = calculate_strike_amount(
+            50_000000000,
+            100_00000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(quote, 5000_000000);
+
+        let quote = calculate_strike_amount(
+            10_000000,
+            2_00000000,
+            USDC_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(quote, 20_000000);
+
+        let quote = calculate_strike_amount(
+            10_00,
+            6_00000000,
+            USDC_DECIMALS,
+            TEST_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(quote, 60_000000);
+
+        let quote = calculate_strike_amount(
+            10_000000,
+            2_00000000,
+            TEST_DECIMALS,
+            SRM_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(quote, 20_00);
+    }
+
+    #[test]
+    fn test_calculate_collateral() {
+        let collateral =
+            calculate_collateral(10_000000000, 10_000000000, 10_000000000, Rounding::Floor)
+                .unwrap();
+        assert_eq!(collateral, 10_000000000);
+
+        let collateral =
+            calculate_collateral(5_000000000, 10_000000000, 10_000000000, Rounding::Floor).unwrap();
+        assert_eq!(collateral, 5_000000000);
+
+        let collateral =
+            calculate_collateral(10_000000000, 6_000000000, 10_000000000, Rounding::Floor).unwrap();
+        assert_eq!(collateral, 6_000000000);
+
+        let collateral =
+            calculate_collateral(5_000000000, 6_000000000, 10_000000000, Rounding::Floor).unwrap();
+        assert_eq!(collateral, 3_000000000);
+    }
+
+    #[test]
+    fn test_calculate_time_weighted_price() {
+        let price = calculate_time_weighted_price(0, 100_00000000, 1000, 1000, 2800).unwrap();
+        assert_eq!(price, 100_00000000);
+
+        let price = calculate_time_weighted_price(0, 100_00000000, 2800, 2800, 2800).unwrap();
+        assert_eq!(price, 100_00000000);
+
+        // 100 for 600 seconds, then 110 for 600 seconds, then 120 until the end
+        let price = calculate_time_weighted_price(
+            100_00000000 * 600 + 110_00000000 * 600,
+            120_00000000,
+            1000,
+            2200,
+            2800,
+        )
+        .unwrap();
+        assert_eq!(price, 110_00000000);
+
+        // A late outlier only weighs for the time it was the latest sample
+        let price =
+            calculate_time_weighted_price(100_00000000 * 1790, 1000_00000000, 1000, 2790, 2800)
+                .unwrap();
+        assert_eq!(price, 105_00000000);
+    }
+
+    #[test]
+    fn test_calculate_average_price() {
+        let price = calculate_average_price(0, 0);
+        assert!(matches!(price, Err(ErrorCode::DivideByZero)));
+
+        let price = calculate_average_price(100_00000000, 1).unwrap();
+        assert_eq!(price, 100_00000000);
+
+        let price = calculate_average_price(100_00000000 + 110_00000000 + 120_00000000, 3).unwrap();
+        assert_eq!(price, 110_00000000);
+
+        // Unlike the time-weighted price, every sample weighs the same regardless of when it was taken
+        let price = calculate_average_price(100_00000000 * 9 + 1000_00000000, 10).unwrap();
+        assert_eq!(price, 190_00000000);
+
+        let price = calculate_average_price(u64::MAX as u128 * 1000, 1000).unwrap();
+        assert_eq!(price, u64::MAX);
+    }
+
+    #[test]
+    fn test_calculate_relative_strike() {
+        let strike = calculate_relative_strike(101_37000000, 10000, 0).unwrap();
+        assert_eq!(strike, 101_37000000);
+
+        let strike = calculate_relative_strike(101_37000000, 11000, 0).unwrap();
+        assert_eq!(strike, 111_50700000);
+
+        let strike = calculate_relative_strike(101_37000000, 11000, 5_00000000).unwrap();
+        assert_eq!(strike, 110_00000000);
+
+        let strike = calculate_relative_strike(101_37000000, 9000, 5_00000000).unwrap();
+        assert_eq!(strike, 90_00000000);
+
+        let strike = calculate_relative_strike(103_00000000, 10000, 5_00000000).unwrap();
+        assert_eq!(strike, 105_00000000);
+
+        // Far out of the money strikes never round down to zero
+        let strike = calculate_relative_strike(1_00000000, 100, 5_00000000).unwrap();
+        assert_eq!(strike, 5_00000000);
+    }
+
+    #[test]
+    fn test_calculate_fee() {
+        let fee = calculate_fee(1000_000000, 0, Rounding::Floor).unwrap();
+        assert_eq!(fee, 0);
+
+        let fee = calculate_fee(1000_000000, 30, Rounding::Floor).unwrap();
+        assert_eq!(fee, 3_000000);
+
+        let fee = calculate_fee(1_000000000, 25, Rounding::Floor).unwrap();
+        assert_eq!(fee, 2500000);
+
+        let fee = calculate_fee(10_00, 30, Rounding::Floor).unwrap();
+        assert_eq!(fee, 3);
+
+        let fee = calculate_fee(333, 30, Rounding::Floor).unwrap();
+        assert_eq!(fee, 0);
+
+        let fee = calculate_fee(u64::MAX, 1000, Rounding::Floor).unwrap();
+        assert_eq!(fee, u64::MAX / 10);
+    }
+
+    #[test]
+    fn test_calculate_median_price() {
+        let price = calculate_median_price(&mut []);
+        assert!(matches!(price, Err(ErrorCode::DivideByZero)));
+
+        let price = calculate_median_price(&mut [100_00000000]).unwrap();
+        assert_eq!(price, 100_00000000);
+
+        let price = calculate_median_price(&mut [100_00000000, 101_00000000]).unwrap();
+        assert_eq!(price, 100_50000000);
+
+        // An outlier among three sources does not move the price
+        let price =
+            calculate_median_price(&mut [1000_00000000, 100_00000000, 101_00000000]).unwrap();
+        assert_eq!(price, 101_00000000);
+
+        let price = calculate_median_price(&mut [u64::MAX, u64::MAX]).unwrap();
+        assert_eq!(price, u64::MAX);
+    }
+
+    #[test]
+    fn test_checked_math_errors() {
+        // Amounts that no longer fit in a token amount are rejected instead of truncated
+        let options = calculate_option_amount(
+            u64::MAX,
+            1,
+            true,
+            0,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        );
+        assert!(matches!(options, Err(ErrorCode::MathOverflow)));
+
+        let collateral = calculate_binary_collateral_amount(u64::MAX, u64::MAX, 0, Rounding::Floor);
+        assert!(matches!(collateral, Err(ErrorCode::MathOverflow)));
+
+        let quote = calculate_strike_amount(u64::MAX, u64::MAX, 30, 0, 0, Rounding::Floor);
+        assert!(matches!(quote, Err(ErrorCode::MathOverflow)));
+
+        // A zero price or empty pool cannot be divided by
+        let options = calculate_option_amount(
+            1000_000000,
+            0,
+            true,
+            0,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        );
+        assert!(matches!(options, Err(ErrorCode::DivideByZero)));
+
+        let options = calculate_binary_option_amount(1000_000000, 0, SOL_DECIMALS, Rounding::Floor);
+        assert!(matches!(options, Err(ErrorCode::DivideByZero)));
+
+        let collateral = calculate_collateral(1, 1000, 0, Rounding::Floor);
+        assert!(matches!(collateral, Err(ErrorCode::DivideByZero)));
+
+        // A spread whose strikes are inverted underflows instead of wrapping
+        let options = calculate_spread_option_amount(
+            1000_000000,
+            100_00000000,
+            110_00000000,
+            true,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        );
+        assert!(matches!(options, Err(ErrorCode::MathUnderflow)));
+
+        // The window cannot end before the last sample was recorded
+        let price = calculate_time_weighted_price(0, 100_00000000, 1000, 2000, 1500);
+        assert!(matches!(price, Err(ErrorCode::MathUnderflow)));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let collateral = calculate_collateral(1, 10, 3, Rounding::Floor).unwrap();
+        assert_eq!(collateral, 3);
+
+        let collateral = calculate_collateral(1, 10, 3, Rounding::Ceil).unwrap();
+        assert_eq!(collateral, 4);
+
+        // Exact divisions are not rounded up
+        let collateral = calculate_collateral(1, 9, 3, Rounding::Ceil).unwrap();
+        assert_eq!(collateral, 3);
+
+        let fee = calculate_fee(333, 30, Rounding::Ceil).unwrap();
+        assert_eq!(fee, 1);
+
+        let quote = calculate_strike_amount(
+            1,
+            101_37000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Floor,
+        )
+        .unwrap();
+        assert_eq!(quote, 0);
+
+        let quote = calculate_strike_amount(
+            1,
+            101_37000000,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            PYTH_USD_EXPONENT,
+            Rounding::Ceil,
+        )
+        .unwrap();
+        assert_eq!(quote, 1);
+    }
+
+    /// Deterministic xorshift generator so randomized tests are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, low: u64, high: u64) -> u64 {
+            low + self.next() % (high - low)
+        }
+    }
+
+    /// A random option market and the decimals of its collateral token
+    fn random_market(rng: &mut Rng) -> (OptionMarket, u8) {
+        let strike_price = rng.range(1, 100000_00000000);
+        let is_put = rng.next() & 1 == 0;
+        let collateral_decimals = if is_put { USDC_DECIMALS } else { SOL_DECIMALS };
+        let market = OptionMarket {
+            strike_price,
+            is_put,
+            price_exponent: PYTH_USD_EXPONENT,
+            ..Default::default()
+        };
+
+        match rng.next() % 4 {
+            // Vanilla
+            0 => (market, collateral_decimals),
+            // Call with a max payout, collateralized in the quote token
+            1 => (
+                OptionMarket {
+                    is_put: false,
+                    max_payout: rng.range(1, 100000_00000000),
+                    ..market
+                },
+                USDC_DECIMALS,
+            ),
+            // Spread
+            2 if is_put => (
+                OptionMarket {
+                    cap_strike: rng.range(0, strike_price),
+                    ..market
+                },
+                collateral_decimals,
+            ),
+            2 => (
+                OptionMarket {
+                    cap_strike: rng.range(strike_price + 1, strike_price * 2 + 2),
+                    ..market
+                },
+                collateral_decimals,
+            ),
+            // Binary
+            _ => (
+                OptionMarket {
+                    payout_kind: PayoutKind::Binary,
+                    binary_payout: rng.range(1, 1000_000000),
+                    ..market
+                },
+                USDC_DECIMALS,
+            ),
+        }
+    }
+
+    /// The parameters of a market as (strike, cap_strike, is_put, max_payout, binary_payout) to report failures
+    fn params(market: &OptionMarket) -> (u64, u64, bool, u64, u64) {
+        (
+            market.strike_price,
+            market.cap_strike,
+            market.is_put,
+            market.max_payout,
+            market.binary_payout,
+        )
+    }
+
+    #[test]
+    fn test_mint_burn_never_drains_vault() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..10000 {
+            let (market, collateral_decimals) = random_market(&mut rng);
+            let mut vault = 0;
+            let mut minted = 0;
+
+            // Several writers mint, then burn in chunks that do not match their deposits
+            for _ in 0..4 {
+                let collateral = rng.range(1, 100000_000000);
+                vault += collateral;
+                minted += market
+                    .option_amount(
+                        collateral,
+                        collateral_decimals,
+                        SOL_DECIMALS,
+                        Rounding::Floor,
+                    )
+                    .unwrap();
+            }
+
+            while minted > 0 {
+                let options = rng.range(1, minted + 1);
+                let collateral = market
+                    .collateral_amount(options, collateral_decimals, SOL_DECIMALS, Rounding::Floor)
+                    .unwrap();
+                assert!(
+                    collateral <= vault,
+                    "{:?} drained the vault",
+                    params(&market)
+                );
+                vault -= collateral;
+                minted -= options;
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_required_collateral() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            exercised_options: 500000000,
+            ..Default::default()
+        };
+
+        // Options exercised early no longer need to be backed
+        let required = calculate_required_collateral(
+            &market,
+            1_500000000,
+            2_000000000,
+            0,
+            USDC_DECIMALS,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+        )
+        .unwrap();
+        assert_eq!(required, (150_000000, 0, false));
+
+        // Knocked out writers are owed the collateral backing their notes
+        let knocked_out = OptionMarket {
+            barrier_kind: BarrierKind::KnockOut,
+            knocked: true,
+            ..market.clone()
+        };
+        let required = calculate_required_collateral(
+            &knocked_out,
+            1_500000000,
+            1_500000000,
+            0,
+            USDC_DECIMALS,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+        )
+        .unwrap();
+        assert_eq!(required, (100_000000, 0, true));
+
+        // Settled holders are owed their payout and writers their share of the snapshot
+        let mut settled = OptionMarket {
+            expiry_price: 50_00000000,
+            exercised_options: 0,
+            ..market.clone()
+        };
+        settled
+            .snapshot_settlement(200_000000, 2_000000000, USDC_DECIMALS, SOL_DECIMALS)
+            .unwrap();
+        let required = calculate_required_collateral(
+            &settled,
+            2_000000000,
+            1_000000000,
+            0,
+            USDC_DECIMALS,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+        )
+        .unwrap();
+        assert_eq!(required, (150_000000, 0, true));
+
+        // Physical writers share the unexercised collateral and the delivered tokens pro rata
+        let physical = OptionMarket {
+            settlement_style: SettlementStyle::Physical,
+            exercise_window: 3600,
+            exercised_options: 1_000000000,
+            ..market
+        };
+        let required = calculate_required_collateral(
+            &physical,
+            1_000000000,
+            2_000000000,
+            0,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+        )
+        .unwrap();
+        assert_eq!(required, (100_000000, 1_000000000, false));
+
+        let required = calculate_required_collateral(
+            &physical,
+            1_000000000,
+            1_000000000,
+            3601,
+            USDC_DECIMALS,
+            SOL_DECIMALS,
+            SOL_DECIMALS,
+        )
+        .unwrap();
+        assert_eq!(required, (50_000000, 500000000, true));
+    }
+
+    /// Notes of a simulated cash settled option, moving collateral the way the instructions do
+    struct SimulatedVault {
+        market: OptionMarket,
+        collateral_decimals: u8,
+        vault_amount: u64,
+        long_supply: u64,
+        short_supply: u64,
+    }
+
+    impl SimulatedVault {
+        fn new(market: OptionMarket, collateral_decimals: u8) -> Self {
+            SimulatedVault {
+                market,
+                collateral_decimals,
+                vault_amount: 0,
+                long_supply: 0,
+                short_supply: 0,
+            }
+        }
+
+        fn mint(&mut self, collateral: u64) -> Result<(), ErrorCode> {
+            // Writers mint the second before expiry
+            self.market
+                .check_can_mint(self.market.expiry_timestamp - 1)?;
+
+            let options = self
+                .market
+                .option_amount(
+                    collateral,
+                    self.collateral_decimals,
+                    SOL_DECIMALS,
+                    Rounding::Floor,
+                )
+                .unwrap();
+            self.vault_amount += collateral;
+            self.long_supply += options;
+            self.short_supply += options;
+            Ok(())
+        }
+
+        fn burn(&mut self, options: u64) -> u64 {
+            let collateral = self
+                .market
+                .collateral_amount(
+                    options,
+                    self.collateral_decimals,
+                    SOL_DECIMALS,
+                    Rounding::Floor,
+                )
+                .unwrap()
+                .min(
+                    calculate_collateral(
+                        options,
+                        self.vault_amount,
+                        self.short_supply,
+                        Rounding::Floor,
+                    )
+                    .unwrap(),
+                );
+            self.vault_amount -= collateral;
+            self.long_supply -= options;
+            self.short_supply -= options;
+            collateral
+        }
+
+        fn exercise(&mut self, options: u64, price: u64) {
+            self.vault_amount -= self
+                .market
+                .expired_value(
+                    options,
+                    price,
+                    self.collateral_decimals,
+                    SOL_DECIMALS,
+                    Rounding::Floor,
+                )
+                .unwrap();
+            self.long_supply -= options;
+            self.market.exercised_options += options;
+        }
+
+        fn settle(&mut self, expiry_price: u64) {
+            self.market.expiry_price = expiry_price;
+            self.market
+                .snapshot_settlement(
+                    self.vault_amount,
+                    self.short_supply,
+                    self.collateral_decimals,
+                    SOL_DECIMALS,
+                )
+                .unwrap();
+        }
+
+        fn redeem(&mut self, options: u64) -> u64 {
+            let payout = self
+                .market
+                .expired_value(
+                    options,
+                    self.market.expiry_price,
+                    self.collateral_decimals,
+                    SOL_DECIMALS,
+                    Rounding::Floor,
+                )
+                .unwrap();
+            self.vault_amount -= payout;
+            self.long_supply -= options;
+            payout
+        }
+
+        fn knock_out(&mut self) {
+            self.market.barrier_kind = BarrierKind::KnockOut;
+            self.market.knocked = true;
+        }
+
+        fn withdraw(&mut self, options: u64) -> u64 {
+            let collateral = if self.market.is_knocked_out() {
+                calculate_collateral(
+                    options,
+                    self.vault_amount,
+                    self.short_supply,
+                    Rounding::Floor,
+                )
+            } else {
+                self.market.settled_collateral_amount(options)
+            }
+            .unwrap();
+            self.vault_amount -= collateral;
+            self.short_supply -= options;
+            collateral
+        }
+
+        fn required_collateral(&self) -> u64 {
+            let (required_collateral, _, _) = calculate_required_collateral(
+                &self.market,
+                self.long_supply,
+                self.short_supply,
+                0,
+                self.collateral_decimals,
+                self.collateral_decimals,
+                SOL_DECIMALS,
+            )
+            .unwrap();
+            required_collateral
+        }
+
+        fn assert_solvent(&self) {
+            let required = self.required_collateral();
+            assert!(
+                self.vault_amount >= required,
+                "{:?} holds {} of {} required",
+                params(&self.market),
+                self.vault_amount,
+                required
+            );
+        }
+    }
+
+    #[test]
+    fn test_redeem_withdraw_never_drains_vault() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..10000 {
+            let (market, collateral_decimals) = random_market(&mut rng);
+            let mut vault = SimulatedVault::new(market, collateral_decimals);
+            vault.mint(rng.range(1, 100000_000000)).unwrap();
+            vault.settle(rng.range(1, 200000_00000000));
+
+            // Holders redeem and writers withdraw in chunks, each rounded down
+            let mut calls = 0;
+            while vault.long_supply > 0 || vault.short_supply > 0 {
+                if vault.long_supply > 0 {
+                    vault.redeem(rng.range(1, vault.long_supply + 1));
+                }
+                if vault.short_supply > 0 {
+                    vault.withdraw(rng.range(1, vault.short_supply + 1));
+                }
+                calls += 2;
+            }
+
+            // Rounding leaves at most a token of dust per call, swept when the market is closed
+            assert!(
+                vault.vault_amount <= calls + 1,
+                "{:?} left {} behind",
+                params(&vault.market),
+                vault.vault_amount
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_sequences_stay_solvent() {
+        let mut rng = Rng(0x853c_49e6_748f_ea9b);
+
+        for _ in 0..2000 {
+            let (market, collateral_decimals) = random_market(&mut rng);
+            let mut vault = SimulatedVault::new(market, collateral_decimals);
+
+            // Writers mint and burn while holders exercise early
+            for _ in 0..20 {
+                match rng.next() % 3 {
+                    0 if vault.market.exercised_options == 0 => {
+                        vault.mint(rng.range(1, 100000_000000)).unwrap()
+                    }
+                    1 if vault.long_supply > 0 => {
+                        vault.burn(rng.range(1, vault.long_supply + 1));
+                    }
+                    2 if vault.long_supply > 0 => vault.exercise(
+                        rng.range(1, vault.long_supply + 1),
+                        rng.range(1, 200000_00000000),
+                    ),
+                    _ => continue,
+                }
+                vault.assert_solvent();
+            }
+
+            // Knocked out options are worthless, the others settle at a random price
+            if rng.next() & 3 == 0 {
+                vault.knock_out();
+            } else {
+                vault.settle(rng.range(1, 200000_00000000));
+            }
+            vault.assert_solvent();
+
+            // Holders redeem and writers withdraw in any order
+            while vault.long_supply > 0 || vault.short_supply > 0 {
+                if vault.short_supply == 0 || (vault.long_supply > 0 && rng.next() & 1 == 0) {
+                    vault.redeem(rng.range(1, vault.long_supply + 1));
+                } else {
+                    vault.withdraw(rng.range(1, vault.short_supply + 1));
+                }
+                vault.assert_solvent();
+            }
+        }
+    }
+
+    #[test]
+    fn test_withdraw_is_order_independent() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            ..Default::default()
+        };
+
+        let mut redeemed_first = SimulatedVault::new(market.clone(), USDC_DECIMALS);
+        redeemed_first.mint(100_000000).unwrap();
+        redeemed_first.mint(100_000000).unwrap();
+        redeemed_first.settle(50_00000000);
+
+        let mut withdrawn_first = SimulatedVault::new(market, USDC_DECIMALS);
+        withdrawn_first.mint(100_000000).unwrap();
+        withdrawn_first.mint(100_000000).unwrap();
+        withdrawn_first.settle(50_00000000);
+
+        assert_eq!(redeemed_first.redeem(1_000000000), 50_000000);
+        assert_eq!(redeemed_first.redeem(1_000000000), 50_000000);
+        assert_eq!(redeemed_first.withdraw(1_000000000), 50_000000);
+        assert_eq!(redeemed_first.withdraw(1_000000000), 50_000000);
+        assert_eq!(redeemed_first.vault_amount, 0);
+
+        // Writers withdrawing first leave the payout of the holders behind
+        assert_eq!(withdrawn_first.withdraw(1_000000000), 50_000000);
+        withdrawn_first.assert_solvent();
+        assert_eq!(withdrawn_first.withdraw(1_000000000), 50_000000);
+        withdrawn_first.assert_solvent();
+        assert_eq!(withdrawn_first.redeem(1_000000000), 50_000000);
+        assert_eq!(withdrawn_first.redeem(1_000000000), 50_000000);
+        assert_eq!(withdrawn_first.vault_amount, 0);
+    }
+
+    #[test]
+    fn test_settle_after_knock_out_withdrawals() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            is_american: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            ..Default::default()
+        };
+
+        let mut vault = SimulatedVault::new(market, USDC_DECIMALS);
+        vault.mint(200_000000).unwrap();
+        vault.exercise(1_000000000, 50_00000000);
+        vault.knock_out();
+
+        // Writers withdraw before expiry until fewer short notes are left than were exercised
+        assert_eq!(vault.withdraw(1_500000000), 112_500000);
+        vault.settle(50_00000000);
+        assert_eq!(vault.market.settled_payout, 0);
+        assert_eq!(vault.withdraw(500000000), 37_500000);
+        assert_eq!(vault.vault_amount, 0);
+
+        // Nothing is left to share once every short note was withdrawn
+        vault.settle(50_00000000);
+        assert_eq!(vault.market.settled_collateral_amount(0).unwrap(), 0);
+        vault.assert_solvent();
+    }
+
+    #[test]
+    fn test_mint_after_exercise() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            is_american: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            ..Default::default()
+        };
+
+        let mut vault = SimulatedVault::new(market, USDC_DECIMALS);
+        vault.mint(200_000000).unwrap();
+        vault.exercise(1_000000000, 50_00000000);
+        assert_eq!(vault.vault_amount, 150_000000);
+
+        // A new writer would otherwise bear part of the payout of the exercised option when burning
+        let minted = vault.mint(100_000000);
+        assert!(matches!(minted, Err(ErrorCode::OptionsExercised)));
+        assert_eq!(vault.vault_amount, 150_000000);
+        assert_eq!(vault.short_supply, 2_000000000);
+
+        // The writer of the exercised option burns the rest and bears its payout
+        assert_eq!(vault.burn(1_000000000), 75_000000);
+        assert_eq!(vault.vault_amount, 75_000000);
+        vault.assert_solvent();
+    }
+
+    #[test]
+    fn test_barrier_breach() {
+        // A barrier above the strike is breached from below
+        let up = OptionMarket {
+            strike_price: 100_00000000,
+            barrier_price: 120_00000000,
+            barrier_kind: BarrierKind::KnockOut,
+            ..Default::default()
+        };
+        assert!(!up.is_barrier_breached(119_99999999));
+        assert!(up.is_barrier_breached(120_00000000));
+        assert!(up.is_barrier_breached(150_00000000));
+
+        // A barrier below the strike is breached from above
+        let down = OptionMarket {
+            barrier_price: 80_00000000,
+            ..up
+        };
+        assert!(!down.is_barrier_breached(80_00000001));
+        assert!(down.is_barrier_breached(80_00000000));
+        assert!(down.is_barrier_breached(0));
+    }
+
+    #[test]
+    fn test_barrier_activation() {
+        let vanilla = OptionMarket::default();
+        assert!(vanilla.is_active());
+        assert!(!vanilla.is_knocked_out());
+
+        let knock_out = OptionMarket {
+            barrier_kind: BarrierKind::KnockOut,
+            ..Default::default()
+        };
+        assert!(knock_out.is_active());
+        assert!(!knock_out.is_knocked_out());
+
+        let knocked_out = OptionMarket {
+            knocked: true,
+            ..knock_out
+        };
+        assert!(!knocked_out.is_active());
+        assert!(knocked_out.is_knocked_out());
+
+        let knock_in = OptionMarket {
+            barrier_kind: BarrierKind::KnockIn,
+            ..Default::default()
+        };
+        assert!(!knock_in.is_active());
+        assert!(!knock_in.is_knocked_out());
+
+        let knocked_in = OptionMarket {
+            knocked: true,
+            ..knock_in
+        };
+        assert!(knocked_in.is_active());
+        assert!(!knocked_in.is_knocked_out());
+    }
+
+    #[test]
+    fn test_barrier_expired_value() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            barrier_price: 60_00000000,
+            ..Default::default()
+        };
+        let payout = |market: &OptionMarket| {
+            market
+                .expired_value(
+                    1_000000000,
+                    50_00000000,
+                    USDC_DECIMALS,
+                    SOL_DECIMALS,
+                    Rounding::Floor,
+                )
+                .unwrap()
+        };
+
+        let knock_out = OptionMarket {
+            barrier_kind: BarrierKind::KnockOut,
+            ..market.clone()
+        };
+        assert_eq!(payout(&knock_out), 50_000000);
+
+        let knocked_out = OptionMarket {
+            knocked: true,
+            ..knock_out
+        };
+        assert_eq!(payout(&knocked_out), 0);
+
+        let knock_in = OptionMarket {
+            barrier_kind: BarrierKind::KnockIn,
+            ..market
+        };
+        assert_eq!(payout(&knock_in), 0);
+
+        let knocked_in = OptionMarket {
+            knocked: true,
+            ..knock_in
+        };
+        assert_eq!(payout(&knocked_in), 50_000000);
+    }
+
+    #[test]
+    fn test_withdraw_after_knock_out() {
+        let market = OptionMarket {
+            strike_price: 100_00000000,
+            is_put: true,
+            price_exponent: PYTH_USD_EXPONENT,
+            barrier_kind: BarrierKind::KnockOut,
+            barrier_price: 60_00000000,
+            ..Default::default()
+        };
+
+        let mut vault = SimulatedVault::new(market, USDC_DECIMALS);
+        vault.mint(100_000000).unwrap();
+        vault.mint(200_000000).unwrap();
+        vault.knock_out();
+        vault.assert_solvent();
+
+        // Worthless long notes redeem for nothing and writers share the whole vault
+        assert_eq!(vault.redeem(3_000000000), 0);
+        assert_eq!(vault.withdraw(1_000000000), 100_000000);
+        vault.assert_solvent();
+        assert_eq!(vault.withdraw(2_000000000), 200_000000);
+        assert_eq!(vault.vault_amount, 0);
+
+        let minted = vault.mint(100_000000);
+        assert!(matches!(minted, Err(ErrorCode::OptionKnockedOut)));
+    }
+
+    #[test]
+    fn test_market_address() {
+        let base_mint = Pubkey::new_unique();
+        let collateral_mint = Pubkey::new_unique();
+        let exercise_mint = Pubkey::new_unique();
+        let oracle_price = Pubkey::new_unique();
+        let params = OptionParams {
+            strike_price: 100_00000000,
+            price_exponent: PYTH_USD_EXPONENT,
+            expiry_timestamp: 1_700_000_000,
+            is_put: true,
+            max_staleness_slots: 25,
+            ..Default::default()
+        };
+        let address = |params: &OptionParams| {
+            find_market_address(
+                &base_mint,
+                &collateral_mint,
+                &exercise_mint,
+                &oracle_price,
+                params,
+            )
+            .0
+        };
+
+        // The address is derived from the same seeds the market is initialized with
+        let seed = market_seed(
+            &base_mint,
+            &collateral_mint,
+            &exercise_mint,
+            &oracle_price,
+            &params,
+        );
+        let (expected, _) = Pubkey::find_program_address(&[b"market", &seed], &crate::ID);
+        assert_eq!(address(&params), expected);
+
+        // Oracle guards do not create another market for the same option
+        let guarded = OptionParams {
+            max_staleness_slots: 24,
+            max_confidence_bps: 100,
+            min_sample_spacing: 60,
+            ..params.clone()
+        };
+        assert_eq!(address(&guarded), address(&params));
+
+        // Economic terms do
+        let other_strike = OptionParams {
+            strike_price: 110_00000000,
+            ..params.clone()
+        };
+        assert_ne!(address(&other_strike), address(&params));
+
+        let american = OptionParams {
+            is_american: true,
+            ..params.clone()
+        };
+        assert_ne!(address(&american), address(&params));
+
+        let other_exponent = OptionParams {
+            price_exponent: PYTH_USD_EXPONENT - 1,
+            ..params.clone()
+        };
+        assert_ne!(address(&other_exponent), address(&params));
+
+        let other_collateral = find_market_address(
+            &base_mint,
+            &base_mint,
+            &exercise_mint,
+            &oracle_price,
+            &params,
+        )
+        .0;
+        assert_ne!(other_collateral, address(&params));
+
+        // So does the token delivered on physical exercise
+        let other_exercise_mint = find_market_address(
+            &base_mint,
+            &collateral_mint,
+            &Pubkey::new_unique(),
+            &oracle_price,
+            &params,
+        )
+        .0;
+        assert_ne!(other_exercise_mint, address(&params));
+    }
+
+    #[test]
+    fn test_oracle_exponent() {
+        let price = OraclePrice {
+            price: 123_45678901,
+            conf: 5_00000000,
+            expo: PYTH_USD_EXPONENT,
+            publish_slot: 0,
+        };
+
+        let scaled = price.scale_to_exponent(-6).unwrap();
+        assert_eq!(
+            (scaled.price, scaled.conf, scaled.expo),
+            (123_456789, 5_000000, -6)
+        );
+        let scaled = price.scale_to_exponent(-10).unwrap();
+        assert_eq!((scaled.price, scaled.conf), (123_4567890100, 5_0000000000));
+
+        // Exponents too far apart to rescale fail instead of overflowing
+        assert!(price.scale_to_exponent(MIN_PRICE_EXPONENT * 2).is_err());
+        assert!(price.scale_to_exponent(i32::MAX).is_err());
+
+        assert!(check_exponent(0).is_ok());
+        assert!(check_exponent(MIN_PRICE_EXPONENT).is_ok());
+        assert!(check_exponent(1).is_err());
+        assert!(check_exponent(MIN_PRICE_EXPONENT - 1).is_err());
+    }
+
+    #[test]
+    fn test_switchboard_rescale() {
+        // The scale of a Switchboard result changes from round to round, the price does not
+        for (mantissa, scale) in [(123_456789012, 9), (123_456789012 * 10_i128.pow(15), 24)] {
+            assert_eq!(
+                SwitchboardOracle::rescale_decimal(mantissa, scale, PYTH_USD_EXPONENT),
+                Some(123_45678901)
+            );
+        }
+        assert_eq!(
+            SwitchboardOracle::rescale_decimal(-1234, 1, -3),
+            Some(-123_400)
+        );
+        assert_eq!(SwitchboardOracle::rescale_decimal(5, 2, 0), Some(0));
+
+        // Mantissas are rescaled as i128 before being narrowed
+        assert_eq!(
+            SwitchboardOracle::rescale_decimal(i128::MAX, 38, MIN_PRICE_EXPONENT),
+            Some((i128::MAX / 10_i128.pow(20)) as i64)
+        );
+        assert_eq!(
+            SwitchboardOracle::rescale_decimal(i128::MAX, u32::MAX, MIN_PRICE_EXPONENT),
+            Some(0)
+        );
+        assert_eq!(
+            SwitchboardOracle::rescale_decimal(i64::MAX as i128, 0, -1),
+            None
+        );
     }
 }
@@ -0,0 +1,336 @@
+use std::convert::TryInto;
+
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::*;
+
+/// Program that owns Switchboard V2 aggregator accounts
+pub mod switchboard_program {
+    anchor_lang::declare_id!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+}
+
+/// Lowest exponent a market or a feed with a fixed exponent can use, so any price can be rescaled between them
+pub const MIN_PRICE_EXPONENT: i32 = -18;
+
+/// Return an error if a price exponent is out of the range supported by markets
+pub fn check_exponent(expo: i32) -> ProgramResult {
+    if !(MIN_PRICE_EXPONENT..=0).contains(&expo) {
+        return Err(ErrorCode::InvalidOracle.into());
+    }
+
+    Ok(())
+}
+
+/// A price read from any oracle backend, in the form `price * 10^expo`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_slot: u64,
+}
+
+impl OraclePrice {
+    /// Reject prices that are too old or too uncertain to settle a market, a zero limit disables a check
+    pub fn check_guards(
+        &self,
+        current_slot: u64,
+        max_staleness_slots: u64,
+        max_confidence_bps: u16,
+    ) -> ProgramResult {
+        if max_staleness_slots > 0
+            && current_slot.saturating_sub(self.publish_slot) > max_staleness_slots
+        {
+            return Err(ErrorCode::StaleOraclePrice.into());
+        }
+
+        // conf / |price| > max_confidence_bps / 10000
+        if max_confidence_bps > 0
+            && (self.conf as u128) * 10000
+                > (max_confidence_bps as u128) * (self.price.unsigned_abs() as u128)
+        {
+            return Err(ErrorCode::OracleConfidenceTooWide.into());
+        }
+
+        Ok(())
+    }
+
+    /// Express the price with a different exponent, returns an error if it no longer fits
+    pub fn scale_to_exponent(&self, expo: i32) -> Result<OraclePrice, ProgramError> {
+        if self.expo == expo {
+            return Ok(*self);
+        }
+
+        let units = match self
+            .expo
+            .checked_sub(expo)
+            .and_then(|decimals| 10_i64.checked_pow(decimals.unsigned_abs()))
+        {
+            Some(val) => val,
+            None => return Err(ErrorCode::PriceError.into()),
+        };
+
+        let (price, conf) = if self.expo > expo {
+            (
+                self.price.checked_mul(units),
+                self.conf.checked_mul(units as u64),
+            )
+        } else {
+            (Some(self.price / units), Some(self.conf / (units as u64)))
+        };
+
+        match (price, conf) {
+            (Some(price), Some(conf)) => Ok(OraclePrice {
+                price,
+                conf,
+                expo,
+                publish_slot: self.publish_slot,
+            }),
+            _ => Err(ErrorCode::PriceError.into()),
+        }
+    }
+}
+
+/// Common interface implemented by every supported price feed
+pub trait PriceOracle {
+    /// Check the accounts describing a feed when a market is created
+    fn validate(price_account: &AccountInfo, product_account: &AccountInfo) -> ProgramResult;
+
+    /// Load the latest price published by the feed, expressed with the exponent of the market
+    fn load_price(price_account: &AccountInfo, expo: i32) -> Result<OraclePrice, ProgramError>;
+}
+
+impl OracleKind {
+    /// Check the accounts describing a feed of this kind
+    pub fn validate(
+        &self,
+        price_account: &AccountInfo,
+        product_account: &AccountInfo,
+    ) -> ProgramResult {
+        match self {
+            OracleKind::Pyth => PythOracle::validate(price_account, product_account),
+            OracleKind::Switchboard => SwitchboardOracle::validate(price_account, product_account),
+            OracleKind::Manual => ManualOracle::validate(price_account, product_account),
+        }
+    }
+
+    /// Load the latest price from a feed of this kind, expressed with the exponent of the market
+    pub fn load_price(
+        &self,
+        price_account: &AccountInfo,
+        expo: i32,
+    ) -> Result<OraclePrice, ProgramError> {
+        match self {
+            OracleKind::Pyth => PythOracle::load_price(price_account, expo),
+            OracleKind::Switchboard => SwitchboardOracle::load_price(price_account, expo),
+            OracleKind::Manual => ManualOracle::load_price(price_account, expo),
+        }
+    }
+}
+
+/// Pyth price accounts, validated against their product account
+pub struct PythOracle;
+
+impl PriceOracle for PythOracle {
+    fn validate(price_account: &AccountInfo, product_account: &AccountInfo) -> ProgramResult {
+        let product_data = product_account.try_borrow_data()?;
+        let product = match pyth_client::load_product(&product_data) {
+            Ok(val) => val,
+            Err(_) => return Err(ErrorCode::PythError.into()),
+        };
+        if read_pyth_product_attribute(&product.attr, b"quote_currency").is_none() {
+            return Err(ErrorCode::InvalidProduct.into());
+        }
+        if product.px_acc.val[..] != price_account.key().to_bytes() {
+            return Err(ErrorCode::InvalidOracle.into());
+        }
+
+        let oracle_data = price_account.try_borrow_data()?;
+        match pyth_client::load_price(&oracle_data) {
+            Ok(val) => check_exponent(val.expo),
+            Err(_) => Err(ErrorCode::PythError.into()),
+        }
+    }
+
+    fn load_price(price_account: &AccountInfo, expo: i32) -> Result<OraclePrice, ProgramError> {
+        let oracle_data = price_account.try_borrow_data()?;
+
+        let oracle = match pyth_client::load_price(&oracle_data) {
+            Ok(val) => val,
+            Err(_) => return Err(ErrorCode::PythError.into()),
+        };
+        let price = match oracle.get_current_price() {
+            None => return Err(ErrorCode::PriceError.into()),
+            Some(val) => val,
+        };
+
+        OraclePrice {
+            price: price.price,
+            conf: price.conf,
+            expo: price.expo,
+            publish_slot: oracle.agg.pub_slot,
+        }
+        .scale_to_exponent(expo)
+    }
+}
+
+/// Switchboard V2 aggregator accounts, read from the latest confirmed round
+pub struct SwitchboardOracle;
+
+impl SwitchboardOracle {
+    /// Anchor discriminator of `AggregatorAccountData`
+    const DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+    /// Offsets into the packed aggregator account, including the discriminator
+    const MIN_ORACLE_RESULTS: usize = 8 + 228;
+    const ROUND_NUM_SUCCESS: usize = 8 + 333;
+    const ROUND_OPEN_SLOT: usize = 8 + 342;
+    const ROUND_RESULT: usize = 8 + 358;
+    const ROUND_STD_DEVIATION: usize = 8 + 378;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn read_u64(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// Read a `SwitchboardDecimal` as its mantissa and scale
+    fn read_decimal(data: &[u8], offset: usize) -> (i128, u32) {
+        let mantissa = i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap());
+        (mantissa, Self::read_u32(data, offset + 16))
+    }
+
+    /// Express a `SwitchboardDecimal` with the given exponent, its scale changes from round to round
+    /// so it is rescaled before being narrowed
+    pub fn rescale_decimal(mantissa: i128, scale: u32, expo: i32) -> Option<i64> {
+        let decimals = (scale as i64).checked_add(expo as i64)?;
+        let units = 10_i128.checked_pow(decimals.unsigned_abs().try_into().ok()?);
+
+        let value = if decimals >= 0 {
+            // Digits below the exponent are dropped, past the range of an i128 nothing is left
+            units.map_or(Some(0), |units| mantissa.checked_div(units))?
+        } else {
+            mantissa.checked_mul(units?)?
+        };
+        value.try_into().ok()
+    }
+
+    fn check_account(price_account: &AccountInfo) -> ProgramResult {
+        if *price_account.owner != switchboard_program::ID {
+            return Err(ErrorCode::InvalidOracle.into());
+        }
+
+        let data = price_account.try_borrow_data()?;
+        if data.len() < Self::ROUND_STD_DEVIATION + 20 || data[..8] != Self::DISCRIMINATOR {
+            return Err(ErrorCode::SwitchboardError.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl PriceOracle for SwitchboardOracle {
+    fn validate(price_account: &AccountInfo, _product_account: &AccountInfo) -> ProgramResult {
+        Self::check_account(price_account)
+    }
+
+    fn load_price(price_account: &AccountInfo, expo: i32) -> Result<OraclePrice, ProgramError> {
+        Self::check_account(price_account)?;
+
+        let data = price_account.try_borrow_data()?;
+
+        if Self::read_u32(&data, Self::MIN_ORACLE_RESULTS)
+            > Self::read_u32(&data, Self::ROUND_NUM_SUCCESS)
+        {
+            return Err(ErrorCode::PriceError.into());
+        }
+
+        let (mantissa, scale) = Self::read_decimal(&data, Self::ROUND_RESULT);
+        let (deviation, deviation_scale) = Self::read_decimal(&data, Self::ROUND_STD_DEVIATION);
+
+        let price = match Self::rescale_decimal(mantissa, scale, expo) {
+            Some(val) => val,
+            None => return Err(ErrorCode::PriceError.into()),
+        };
+        let conf: u64 = match Self::rescale_decimal(deviation.abs(), deviation_scale, expo)
+            .and_then(|val| val.try_into().ok())
+        {
+            Some(val) => val,
+            None => return Err(ErrorCode::PriceError.into()),
+        };
+
+        Ok(OraclePrice {
+            price,
+            conf,
+            expo,
+            publish_slot: Self::read_u64(&data, Self::ROUND_OPEN_SLOT),
+        })
+    }
+}
+
+/// Prices posted by an authority to a `ManualPrice` account owned by this program
+pub struct ManualOracle;
+
+impl ManualOracle {
+    fn load_account(price_account: &AccountInfo) -> Result<ManualPrice, ProgramError> {
+        if *price_account.owner != crate::ID {
+            return Err(ErrorCode::InvalidOracle.into());
+        }
+
+        let data = price_account.try_borrow_data()?;
+        ManualPrice::try_deserialize(&mut &data[..])
+    }
+}
+
+impl PriceOracle for ManualOracle {
+    fn validate(price_account: &AccountInfo, _product_account: &AccountInfo) -> ProgramResult {
+        check_exponent(Self::load_account(price_account)?.expo)
+    }
+
+    fn load_price(price_account: &AccountInfo, expo: i32) -> Result<OraclePrice, ProgramError> {
+        let manual_price = Self::load_account(price_account)?;
+
+        if manual_price.publish_slot == 0 {
+            return Err(ErrorCode::PriceError.into());
+        }
+
+        OraclePrice {
+            price: manual_price.price,
+            conf: manual_price.conf,
+            expo: manual_price.expo,
+            publish_slot: manual_price.publish_slot,
+        }
+        .scale_to_exponent(expo)
+    }
+}
+
+fn read_pyth_product_attribute<'d>(data: &'d [u8], attribute: &[u8]) -> Option<&'d [u8]> {
+    let mut idx = 0;
+
+    while idx < data.len() {
+        let key_len = data[idx] as usize;
+        idx += 1;
+
+        if key_len == 0 {
+            continue;
+        }
+
+        let key = &data[idx..idx + key_len];
+        idx += key_len;
+
+        let val_len = data[idx] as usize;
+        idx += 1;
+
+        let value = &data[idx..idx + val_len];
+        idx += val_len;
+
+        if key == attribute {
+            return Some(value);
+        }
+    }
+
+    None
+}
@@ -1,4 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+
+use crate::errors::ErrorCode;
+use crate::math::*;
 
 /// Option account
 #[account]
@@ -22,29 +26,882 @@ pub struct OptionMarket {
     /// The account with custody over the collateral tokens
     pub vault: Pubkey,
 
+    /// The account accumulating price samples for settlement
+    pub settlement: Pubkey,
+
     /// The bump seed values for pdas
     pub bumps: OptionBumps,
 
-    /// The account where a Pyth oracle keeps the updated price of the token
-    pub pyth_oracle_price: Pubkey,
+    /// The account which paid the rent for the market and is refunded when it is closed
+    pub rent_payer: Pubkey,
+
+    /// The backend used to read prices from the oracle
+    pub oracle_kind: OracleKind,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: Pubkey,
+
+    /// Exponent of the strike and of the oracle prices, chosen by the market creator
+    pub price_exponent: i32,
+
+    /// Additional price sources used to settle the option on the spot price, unused if the price account is default
+    pub fallback_sources: [PriceSource; MAX_FALLBACK_SOURCES],
+
+    /// Minimum number of price sources which must pass the oracle guards to settle on the spot price
+    pub settlement_quorum: u8,
 
-    /// Strike price, must be in the same decimals as the Pyth oracle
+    /// Bitmask of the price sources the expiry price was computed from, bit zero being the primary oracle
+    pub settlement_sources: u8,
+
+    /// Strike price, must be in the same decimals as the price exponent
     pub strike_price: u64,
 
-    /// Expiry price, must be in the same decimals as the Pyth oracle
+    /// Strike at which the payout of a spread is capped, zero for a vanilla option
+    pub cap_strike: u64,
+
+    /// Expiry price, must be in the same decimals as the price exponent
     pub expiry_price: u64,
 
+    /// Expiry price first recorded at settlement, before any override by the guardian
+    pub original_expiry_price: u64,
+
+    /// Timestamp until which the expiry price can be disputed and payouts are blocked
+    pub dispute_end_timestamp: i64,
+
+    /// Collateral held in the vault when the option was settled
+    pub settled_collateral: u64,
+
+    /// Short notes outstanding when the option was settled
+    pub settled_options: u64,
+
+    /// Payout owed at the expiry price to the long notes outstanding when the option was settled
+    pub settled_payout: u64,
+
     /// Expiry timestamp
     pub expiry_timestamp: i64,
 
     /// False if the option is a put, True if the option is a call
     pub is_put: bool,
+
+    /// True if the option can be exercised before expiry
+    pub is_american: bool,
+
+    /// Whether the option pays out in collateral or by delivery of the underlying
+    pub settlement_style: SettlementStyle,
+
+    /// Whether the payout grows with the price or is a fixed amount
+    pub payout_kind: PayoutKind,
+
+    /// Collateral paid per whole binary option finishing in the money, zero for other payout kinds
+    pub binary_payout: u64,
+
+    /// Maximum payout per option of a call collateralized in the quote token, in the same decimals
+    /// as the price exponent, zero for a call collateralized in the base token
+    pub max_payout: u64,
+
+    /// Whether crossing the barrier price deactivates or activates the option
+    pub barrier_kind: BarrierKind,
+
+    /// Price which knocks the option in or out when reached, from below if above the strike
+    /// and from above if below the strike, zero if the option has no barrier
+    pub barrier_price: u64,
+
+    /// True once the oracle price was reported crossing the barrier
+    pub knocked: bool,
+
+    /// The mint for the token delivered by long holders on physical exercise,
+    /// the quote token for a call and the base token for a put
+    pub exercise_mint: Pubkey,
+
+    /// The account with custody over the tokens delivered on physical exercise
+    pub exercise_vault: Pubkey,
+
+    /// Seconds after expiry during which long holders can exercise physically settled options
+    pub exercise_window: i64,
+
+    /// Long notes exercised before expiry, the short notes bear their payout pro rata
+    pub exercised_options: u64,
+
+    /// Maximum age in slots of the oracle price used for settlement, zero to disable
+    pub max_staleness_slots: u64,
+
+    /// Maximum oracle confidence interval in basis points of the price used for settlement, zero to disable
+    pub max_confidence_bps: u16,
+
+    /// Seconds before expiry during which prices are sampled for settlement, zero to settle on the spot price
+    pub settlement_window: i64,
+
+    /// Minimum number of price samples required to settle when a settlement window is used,
+    /// the option settles on those recorded after the grace period if fewer were recorded
+    pub min_settlement_samples: u32,
+
+    /// Bitmask of the `PAUSE_*` flags paused for this market
+    pub paused: u8,
+
+    /// True if the option settles on the arithmetic mean of the samples recorded during the
+    /// settlement window (its observation period) instead of their time-weighted average
+    pub is_asian: bool,
+
+    /// Minimum seconds between two price samples, zero to allow a sample every second
+    pub min_sample_spacing: i64,
+}
+
+impl OptionMarket {
+    /// True if the payout of the option is capped at a second strike
+    pub fn is_spread(&self) -> bool {
+        self.cap_strike != 0
+    }
+
+    /// True if the price reached the barrier of the option
+    pub fn is_barrier_breached(&self, price: u64) -> bool {
+        if self.barrier_price > self.strike_price {
+            price >= self.barrier_price
+        } else {
+            price <= self.barrier_price
+        }
+    }
+
+    /// True if a knock-out was recorded, after which the options are worthless
+    pub fn is_knocked_out(&self) -> bool {
+        self.barrier_kind == BarrierKind::KnockOut && self.knocked
+    }
+
+    /// True if the options can pay out given the barrier state
+    pub fn is_active(&self) -> bool {
+        match self.barrier_kind {
+            BarrierKind::None => true,
+            BarrierKind::KnockOut => !self.knocked,
+            BarrierKind::KnockIn => self.knocked,
+        }
+    }
+
+    /// Check that new options can be written, which stops once any option was exercised early
+    /// since the vault no longer holds the full collateral of the notes written so far
+    pub fn check_can_mint(&self, now: i64) -> Result<(), ErrorCode> {
+        if self.expiry_timestamp <= now {
+            Err(ErrorCode::OptionExpired)
+        } else if self.is_knocked_out() {
+            Err(ErrorCode::OptionKnockedOut)
+        } else if self.exercised_options > 0 {
+            Err(ErrorCode::OptionsExercised)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Timestamp after which an option short of settlement samples can settle on those recorded
+    pub fn grace_end_timestamp(&self) -> Result<i64, ErrorCode> {
+        self.expiry_timestamp
+            .checked_add(SETTLEMENT_GRACE_PERIOD)
+            .ok_or(ErrorCode::MathOverflow)
+    }
+
+    /// Timestamp until which the long notes of a physically settled option can be exercised
+    pub fn exercise_end_timestamp(&self) -> Result<i64, ErrorCode> {
+        self.expiry_timestamp
+            .checked_add(self.exercise_window)
+            .ok_or(ErrorCode::MathOverflow)
+    }
+
+    /// Snapshot the vault when the option is settled so every note is paid out the same whatever the order of calls
+    pub fn snapshot_settlement(
+        &mut self,
+        vault_amount: u64,
+        short_supply: u64,
+        collateral_decimals: u8,
+        base_decimals: u8,
+    ) -> Result<(), ErrorCode> {
+        self.settled_collateral = vault_amount;
+        self.settled_options = short_supply;
+        self.update_settled_payout(collateral_decimals, base_decimals)
+    }
+
+    /// Recalculate the payout owed to the long notes at the current expiry price,
+    /// options exercised early already paid out of the vault and have no long notes left
+    pub fn update_settled_payout(
+        &mut self,
+        collateral_decimals: u8,
+        base_decimals: u8,
+    ) -> Result<(), ErrorCode> {
+        // Writers of knocked out options can withdraw before expiry, leaving fewer short notes than were exercised
+        let long_options = self.settled_options.saturating_sub(self.exercised_options);
+
+        self.settled_payout = self.expired_value(
+            long_options,
+            self.expiry_price,
+            collateral_decimals,
+            base_decimals,
+            Rounding::Ceil,
+        )?;
+
+        Ok(())
+    }
+
+    /// Calculate the collateral withdrawn for short notes after settlement,
+    /// their share of what is left in the vault once the payout of the long notes is reserved
+    pub fn settled_collateral_amount(&self, options: u64) -> Result<u64, ErrorCode> {
+        if self.settled_options == 0 {
+            return Ok(0);
+        }
+
+        calculate_collateral(
+            options,
+            self.settled_collateral.saturating_sub(self.settled_payout),
+            self.settled_options,
+            Rounding::Floor,
+        )
+    }
+
+    /// Calculate the amount of options minted for the given collateral
+    pub fn option_amount(
+        &self,
+        collateral: u64,
+        collateral_decimals: u8,
+        base_decimals: u8,
+        rounding: Rounding,
+    ) -> Result<u64, ErrorCode> {
+        if self.payout_kind == PayoutKind::Binary {
+            calculate_binary_option_amount(collateral, self.binary_payout, base_decimals, rounding)
+        } else if self.is_spread() {
+            calculate_spread_option_amount(
+                collateral,
+                self.strike_price,
+                self.cap_strike,
+                self.is_put,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        } else {
+            calculate_option_amount(
+                collateral,
+                self.strike_price,
+                self.is_put,
+                self.max_payout,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        }
+    }
+
+    /// Calculate the amount of collateral backing the given options
+    pub fn collateral_amount(
+        &self,
+        options: u64,
+        collateral_decimals: u8,
+        base_decimals: u8,
+        rounding: Rounding,
+    ) -> Result<u64, ErrorCode> {
+        if self.payout_kind == PayoutKind::Binary {
+            calculate_binary_collateral_amount(options, self.binary_payout, base_decimals, rounding)
+        } else if self.is_spread() {
+            calculate_spread_collateral_amount(
+                options,
+                self.strike_price,
+                self.cap_strike,
+                self.is_put,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        } else {
+            calculate_collateral_amount(
+                options,
+                self.strike_price,
+                self.is_put,
+                self.max_payout,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        }
+    }
+
+    /// Calculate the payout of the given options at a price
+    pub fn expired_value(
+        &self,
+        options: u64,
+        price: u64,
+        collateral_decimals: u8,
+        base_decimals: u8,
+        rounding: Rounding,
+    ) -> Result<u64, ErrorCode> {
+        if !self.is_active() {
+            Ok(0)
+        } else if self.payout_kind == PayoutKind::Binary {
+            calculate_binary_expired_value(
+                options,
+                self.strike_price,
+                price,
+                self.binary_payout,
+                self.is_put,
+                base_decimals,
+                rounding,
+            )
+        } else if self.is_spread() {
+            calculate_spread_expired_value(
+                options,
+                self.strike_price,
+                self.cap_strike,
+                price,
+                self.is_put,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        } else {
+            calculate_expired_value(
+                options,
+                self.strike_price,
+                price,
+                self.is_put,
+                self.max_payout,
+                collateral_decimals,
+                base_decimals,
+                self.price_exponent,
+                rounding,
+            )
+        }
+    }
+}
+
+/// Parameters of a new option
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct OptionParams {
+    /// Strike price, must be in the same decimals as the price exponent
+    pub strike_price: u64,
+
+    /// Exponent of the strike and of every price of the option, oracle prices are rescaled to it
+    pub price_exponent: i32,
+
+    /// Strike at which the payout of a spread is capped, zero for a vanilla option
+    pub cap_strike: u64,
+
+    /// Expiry timestamp
+    pub expiry_timestamp: i64,
+
+    /// False if the option is a put, True if the option is a call
+    pub is_put: bool,
+
+    /// True if the option can be exercised before expiry
+    pub is_american: bool,
+
+    /// Whether the option pays out in collateral or by delivery of the underlying
+    pub settlement_style: SettlementStyle,
+
+    /// Whether the payout grows with the price or is a fixed amount
+    pub payout_kind: PayoutKind,
+
+    /// Collateral paid per whole binary option finishing in the money, zero for other payout kinds
+    pub binary_payout: u64,
+
+    /// Maximum payout per option of a call collateralized in the quote token, in the same decimals
+    /// as the price exponent, zero for a call collateralized in the base token
+    pub max_payout: u64,
+
+    /// Whether crossing the barrier price deactivates or activates the option
+    pub barrier_kind: BarrierKind,
+
+    /// Price which knocks the option in or out when reached, from below if above the strike
+    /// and from above if below the strike, zero if the option has no barrier
+    pub barrier_price: u64,
+
+    /// Seconds after expiry during which long holders can exercise physically settled options
+    pub exercise_window: i64,
+
+    /// The backend used to read prices from the oracle
+    pub oracle_kind: OracleKind,
+
+    /// Additional price sources used to settle the option on the spot price, unused if the price account is default
+    pub fallback_sources: [PriceSource; MAX_FALLBACK_SOURCES],
+
+    /// Minimum number of price sources which must pass the oracle guards to settle on the spot price
+    pub settlement_quorum: u8,
+
+    /// Maximum age in slots of the oracle price used for settlement, zero to disable
+    pub max_staleness_slots: u64,
+
+    /// Maximum oracle confidence interval in basis points of the price used for settlement, zero to disable
+    pub max_confidence_bps: u16,
+
+    /// Seconds before expiry during which prices are sampled for settlement, zero to settle on the spot price
+    pub settlement_window: i64,
+
+    /// Minimum number of price samples required to settle when a settlement window is used,
+    /// the option settles on those recorded after the grace period if fewer were recorded
+    pub min_settlement_samples: u32,
+
+    /// True if the option settles on the arithmetic mean of the samples recorded during the
+    /// settlement window (its observation period) instead of their time-weighted average
+    pub is_asian: bool,
+
+    /// Minimum seconds between two price samples, zero to allow a sample every second
+    pub min_sample_spacing: i64,
+}
+
+impl OptionParams {
+    /// Hash of the oracle guards, settlement sources and exercise window of the option,
+    /// part of the series and schedule addresses which are keyed on every parameter of their markets
+    pub fn guards_seed(&self) -> [u8; 32] {
+        let guards = (
+            self.oracle_kind,
+            self.fallback_sources,
+            self.settlement_quorum,
+            self.max_staleness_slots,
+            self.max_confidence_bps,
+            self.settlement_window,
+            self.min_settlement_samples,
+            self.min_sample_spacing,
+            self.exercise_window,
+        );
+        hash(&guards.try_to_vec().unwrap()).to_bytes()
+    }
+}
+
+/// Hash of the mints, oracle and economic terms of an option, the seed of its market address
+/// so each option can only be listed once whatever oracle guards it is created with
+pub fn market_seed(
+    base_mint: &Pubkey,
+    collateral_mint: &Pubkey,
+    exercise_mint: &Pubkey,
+    oracle_price: &Pubkey,
+    params: &OptionParams,
+) -> [u8; 32] {
+    hashv(&[
+        base_mint.as_ref(),
+        collateral_mint.as_ref(),
+        exercise_mint.as_ref(),
+        oracle_price.as_ref(),
+        &params.strike_price.to_le_bytes(),
+        &params.price_exponent.to_le_bytes(),
+        &params.cap_strike.to_le_bytes(),
+        &params.expiry_timestamp.to_le_bytes(),
+        &[
+            params.is_put as u8,
+            params.is_american as u8,
+            params.settlement_style as u8,
+            params.payout_kind as u8,
+            params.barrier_kind as u8,
+            params.is_asian as u8,
+        ],
+        &params.binary_payout.to_le_bytes(),
+        &params.barrier_price.to_le_bytes(),
+        &params.max_payout.to_le_bytes(),
+        &params.settlement_window.to_le_bytes(),
+        &params.exercise_window.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Find the address of the option market for a series
+pub fn find_market_address(
+    base_mint: &Pubkey,
+    collateral_mint: &Pubkey,
+    exercise_mint: &Pubkey,
+    oracle_price: &Pubkey,
+    params: &OptionParams,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"market",
+            &market_seed(
+                base_mint,
+                collateral_mint,
+                exercise_mint,
+                oracle_price,
+                params,
+            ),
+        ],
+        &crate::ID,
+    )
+}
+
+/// A chain of options on an underlying sharing every parameter but the strike
+#[account]
+#[derive(Default)]
+pub struct OptionSeries {
+    /// The key allowed to add strikes to the series
+    pub authority: Pubkey,
+
+    /// The mint for the base token
+    pub base_mint: Pubkey,
+
+    /// The mint for the token used as collateral
+    pub collateral_mint: Pubkey,
+
+    /// The mint for the token delivered by long holders on physical exercise
+    pub exercise_mint: Pubkey,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: Pubkey,
+
+    /// Parameters shared by the markets of the series, the strike price is set by each market
+    pub params: OptionParams,
+
+    /// Maximum number of strikes the account has space for
+    pub max_strikes: u16,
+
+    /// Markets created for the series
+    pub strikes: Vec<SeriesStrike>,
+}
+
+impl OptionSeries {
+    /// Size of the account data for a series with space for the given number of strikes
+    pub fn space(max_strikes: u16) -> usize {
+        8 + OptionSeries::default().try_to_vec().unwrap().len()
+            + (max_strikes as usize) * SeriesStrike::default().try_to_vec().unwrap().len()
+    }
+}
+
+/// A market of an option series
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct SeriesStrike {
+    /// Strike price of the market
+    pub strike_price: u64,
+
+    /// Option account
+    pub market: Pubkey,
+}
+
+/// A calendar listing options on an underlying at a fixed cadence, with strikes relative to spot
+#[account]
+#[derive(Default)]
+pub struct Schedule {
+    /// The key which created the schedule
+    pub authority: Pubkey,
+
+    /// The mint for the base token
+    pub base_mint: Pubkey,
+
+    /// The mint for the token used as collateral
+    pub collateral_mint: Pubkey,
+
+    /// The mint for the token delivered by long holders on physical exercise
+    pub exercise_mint: Pubkey,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: Pubkey,
+
+    /// Parameters shared by the markets of the schedule, the expiry timestamp is the
+    /// expiry of the markets being listed and the strike price is set by each market
+    pub params: OptionParams,
+
+    /// Seconds between two expiries
+    pub cadence: i64,
+
+    /// Strikes of each expiry in basis points of the spot price when the expiry is listed
+    pub strike_bps: Vec<u32>,
+
+    /// Strikes are rounded to a multiple of this increment, zero to disable
+    pub strike_increment: u64,
+
+    /// Lamports paid out of the schedule account to the caller for each market listed
+    pub crank_reward: u64,
+
+    /// Spot price read when listing the current expiry started, zero if not read yet
+    pub spot_price: u64,
+
+    /// Number of markets of the current expiry listed so far
+    pub num_listed: u32,
+}
+
+impl Schedule {
+    /// Size of the account data for a schedule with the given number of strikes
+    pub fn space(num_strikes: usize) -> usize {
+        8 + Schedule::default().try_to_vec().unwrap().len() + num_strikes * 4
+    }
+}
+
+/// Parameters of a new schedule
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ScheduleParams {
+    /// Seconds between two expiries
+    pub cadence: i64,
+
+    /// Strikes of each expiry in basis points of the spot price when the expiry is listed
+    pub strike_bps: Vec<u32>,
+
+    /// Strikes are rounded to a multiple of this increment, zero to disable
+    pub strike_increment: u64,
+
+    /// Lamports paid out of the schedule account to the caller for each market listed
+    pub crank_reward: u64,
+
+    /// Lamports deposited into the schedule account to fund crank rewards
+    pub reward_deposit: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
 pub struct OptionBumps {
+    pub market: u8,
     pub market_authority: u8,
     pub short_note_mint: u8,
     pub long_note_mint: u8,
     pub vault: u8,
+    pub exercise_vault: u8,
+    pub settlement: u8,
+}
+
+/// Price samples recorded during the settlement window of an option
+#[account]
+#[derive(Default)]
+pub struct Settlement {
+    /// The option this account settles
+    pub market: Pubkey,
+
+    /// Number of price samples recorded
+    pub num_samples: u32,
+
+    /// Timestamp of the first price sample
+    pub first_sample_timestamp: i64,
+
+    /// Timestamp of the latest price sample
+    pub last_sample_timestamp: i64,
+
+    /// Price of the latest price sample
+    pub last_sample_price: u64,
+
+    /// Sum of each sampled price multiplied by the seconds it was the latest sample
+    pub cumulative_price: u128,
+
+    /// Sum of the sampled prices
+    pub price_sum: u128,
+}
+
+/// Maximum number of price sources of an option besides its primary oracle
+pub const MAX_FALLBACK_SOURCES: usize = 2;
+
+/// Time after expiry from which an option whose settlement window lacks samples settles on those recorded,
+/// or on the spot price through the guardian if none were
+pub const SETTLEMENT_GRACE_PERIOD: i64 = 24 * 60 * 60;
+
+/// A price account and the backend used to read it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceSource {
+    /// The backend used to read prices from the oracle
+    pub oracle_kind: OracleKind,
+
+    /// The account where the oracle keeps the updated price of the token
+    pub oracle_price: Pubkey,
+}
+
+/// Supported price oracle backends
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OracleKind {
+    /// Pyth price account
+    #[default]
+    Pyth,
+
+    /// Switchboard V2 aggregator account
+    Switchboard,
+
+    /// `ManualPrice` account updated by an admin
+    Manual,
+}
+
+/// How an option is settled
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SettlementStyle {
+    /// Long holders are paid the intrinsic value in collateral
+    #[default]
+    Cash,
+
+    /// Long holders pay the strike in the quote token for the base token (call),
+    /// or deliver the base token for the strike (put)
+    Physical,
+}
+
+/// How the payout of an option is computed from the settlement price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PayoutKind {
+    /// Long holders are paid the difference between the settlement price and the strike
+    #[default]
+    Vanilla,
+
+    /// Long holders are paid a fixed amount of collateral if the option finishes in the money
+    Binary,
+}
+
+/// How a barrier changes an option once it is breached
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BarrierKind {
+    /// The option has no barrier
+    #[default]
+    None,
+
+    /// The option becomes worthless once the barrier is breached
+    KnockOut,
+
+    /// The option only pays out if the barrier was breached
+    KnockIn,
+}
+
+/// Maximum number of collateral mints in the allow-list of the protocol
+pub const MAX_COLLATERAL_MINTS: usize = 16;
+
+/// Maximum number of oracle accounts in the allow-list of the protocol
+pub const MAX_ORACLES: usize = 64;
+
+/// Maximum fee charged by the protocol in basis points
+pub const MAX_FEE_BPS: u16 = 1000;
+
+/// Pause flag blocking `mint`
+pub const PAUSE_MINT: u8 = 1 << 0;
+
+/// Pause flag blocking `burn`
+pub const PAUSE_BURN: u8 = 1 << 1;
+
+/// Pause flag blocking every instruction reading the oracle to settle an option:
+/// `settle`, `record_price_sample` and `report_barrier_breach`
+pub const PAUSE_SETTLE: u8 = 1 << 2;
+
+/// Pause flag blocking long holders from claiming payouts: `redeem` and both exercise instructions
+pub const PAUSE_REDEEM: u8 = 1 << 3;
+
+/// Pause flag blocking `withdraw`
+pub const PAUSE_WITHDRAW: u8 = 1 << 4;
+
+/// Every pause flag
+pub const PAUSE_ALL: u8 = PAUSE_MINT | PAUSE_BURN | PAUSE_SETTLE | PAUSE_REDEEM | PAUSE_WITHDRAW;
+
+/// Global configuration of the protocol, a singleton PDA
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    /// The key allowed to update the configuration
+    pub admin: Pubkey,
+
+    /// The key the admin handed over to, which becomes admin once it accepts
+    pub pending_admin: Pubkey,
+
+    /// Mints which can be used as collateral by new markets, or delivered on the physical exercise of their calls
+    pub collateral_mints: Vec<Pubkey>,
+
+    /// Oracle price accounts which can be used by new markets
+    pub oracles: Vec<Pubkey>,
+
+    /// Minimum seconds between the creation of a market and its expiry
+    pub min_expiry_distance: i64,
+
+    /// Maximum seconds between the creation of a market and its expiry, zero to disable
+    pub max_expiry_distance: i64,
+
+    /// Fee in basis points of the collateral deposited when minting options
+    pub mint_fee_bps: u16,
+
+    /// Fee in basis points of the payout when redeeming options
+    pub redeem_fee_bps: u16,
+
+    /// Fee in basis points of the collateral withdrawn by writers
+    pub withdraw_fee_bps: u16,
+
+    /// The key allowed to pause the protocol and markets alongside the admin
+    pub guardian: Pubkey,
+
+    /// Bitmask of the `PAUSE_*` flags paused for every market
+    pub paused: u8,
+
+    /// Seconds after settlement during which the guardian can override the expiry price
+    pub dispute_period: i64,
+
+    /// Loosest oracle staleness in slots new markets can settle with, zero to leave it to their creator
+    pub max_staleness_slots: u64,
+
+    /// Widest oracle confidence interval in basis points new markets can settle with, zero to leave it to their creator
+    pub max_confidence_bps: u16,
+
+    /// Fewest price samples new markets with a settlement window can require to settle
+    pub min_settlement_samples: u32,
+}
+
+impl ProtocolConfig {
+    /// Return an error if an operation is paused globally or for the market
+    pub fn check_not_paused(&self, market: &OptionMarket, flag: u8) -> ProgramResult {
+        if (self.paused | market.paused) & flag != 0 {
+            return Err(ErrorCode::Paused.into());
+        }
+
+        Ok(())
+    }
+
+    /// True if the key is the admin or the guardian of the protocol
+    pub fn is_admin_or_guardian(&self, key: &Pubkey) -> bool {
+        *key == self.admin || (*key == self.guardian && self.guardian != Pubkey::default())
+    }
+
+    /// True if the key can change pause flags, the guardian can only pause operations
+    pub fn can_pause(&self, key: &Pubkey, current: u8, paused: u8) -> bool {
+        *key == self.admin
+            || (*key == self.guardian
+                && self.guardian != Pubkey::default()
+                && paused & current == current)
+    }
+
+    /// Size of the account data with space for full allow-lists
+    pub fn space() -> usize {
+        8 + ProtocolConfig::default().try_to_vec().unwrap().len()
+            + (MAX_COLLATERAL_MINTS + MAX_ORACLES) * 32
+    }
+}
+
+/// A change to the protocol configuration
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigUpdate {
+    /// Hand over the admin authority, the new admin must accept it
+    PendingAdmin { admin: Pubkey },
+
+    /// Add or remove a mint from the collateral allow-list
+    CollateralMint { mint: Pubkey, allowed: bool },
+
+    /// Add or remove an oracle price account from the oracle allow-list
+    Oracle { oracle_price: Pubkey, allowed: bool },
+
+    /// Set the bounds of the expiry distance of new markets
+    ExpiryDistance { min: i64, max: i64 },
+
+    /// Set the key allowed to pause the protocol and markets alongside the admin
+    Guardian { guardian: Pubkey },
+
+    /// Set the seconds after settlement during which the expiry price can be disputed
+    DisputePeriod { dispute_period: i64 },
+
+    /// Set the fees charged by the protocol
+    Fees {
+        mint_fee_bps: u16,
+        redeem_fee_bps: u16,
+        withdraw_fee_bps: u16,
+    },
+
+    /// Set the oracle guards new markets must be at least as strict as
+    OracleGuards {
+        max_staleness_slots: u64,
+        max_confidence_bps: u16,
+        min_settlement_samples: u32,
+    },
+}
+
+/// Price account updated by an admin, used when no oracle feed exists for an asset
+#[account]
+#[derive(Default)]
+pub struct ManualPrice {
+    /// The key allowed to post prices
+    pub authority: Pubkey,
+
+    /// Latest posted price
+    pub price: i64,
+
+    /// Confidence interval around the price
+    pub conf: u64,
+
+    /// Exponent of the price and confidence interval
+    pub expo: i32,
+
+    /// Slot at which the price was last posted, zero if no price was posted yet
+    pub publish_slot: u64,
 }